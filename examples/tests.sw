@@ -0,0 +1,357 @@
+test "assertions" {
+    @assert(true);
+    @assert(1 < 2, "one is less than two");
+    int checked = 0;
+    for i in 0..10 {
+        @assert(i >= 0 and i < 10, "{i} is out of range");
+        checked = checked + 1;
+    };
+    @assert(checked == 10);
+};
+
+test "arithmetic" {
+    @assert(1 + 2 == 3);
+    @assert(7 % 3 == 1);
+};
+
+test "strings" {
+    string s = "swindle";
+    @assert(@length(s) == 7);
+    @assert(s[0] == "s");
+    @assert("{s[:3]}-{@length(s[3:])}" == "swi-4");
+    @assert("{{{@length(s)}}}" == "{{7}}");
+    @assert(@length("{{1, 2}}") == 6 and @length("{{}}") == 2);
+    @assert("{ { {@length({int, 1, 2}); }; } }|{s:>9}|" == "2|  swindle|");
+};
+
+test "indexing and slicing" {
+    string s = "swindle";
+    @assert(s[-1] == "e" and s[1:4] == "win" and s[:-3] == "swin" and s[4:] == "dle");
+    @assert(s[5:100] == "le" and s[-100:2] == "sw" and s[4:2] == "");
+    [int] xs = [int; 1, 2, 3];
+    @assert(xs[-3] == 1 and xs[1:] == [int; 2, 3] and @length(xs[:-5]) == 0);
+    xs[-1] = 9;
+    @assert(xs == [int; 1, 2, 9]);
+};
+
+test "lists" {
+    [int] squares = for int i = 0; i < 5; i = i + 1 { i * i; };
+    @assert(squares == [int; 0, 1, 4, 9, 16]);
+};
+
+test "list mutation" {
+    [string] xs = [string];
+    @push(xs, "b");
+    @insert(xs, 0, "a");
+    @insert(xs, 2, "d");
+    @insert(xs, -1, "c");
+    @assert(xs == [string; "a", "b", "c", "d"]);
+    @assert(@pop(xs) == "d" and @remove(xs, -2) == "b" and @remove(xs, 0) == "a");
+    @extend(xs, xs);
+    @assert(xs == [string; "c", "c"]);
+    @clear(xs);
+    @assert(@length(xs) == 0);
+    @push(xs, "e");
+    @assert(@pop(xs) == "e" and @length(xs) == 0);
+};
+
+test "sorting and searching" {
+    [string] words = [string; "pear", "fig", "banana", "kiwi"];
+    @sort(words);
+    @assert(words == [string; "banana", "fig", "kiwi", "pear"]);
+    @sort_by(words, fn(string a, string b) { @length(a) < @length(b); });
+    @assert(words == [string; "fig", "kiwi", "pear", "banana"]);
+    @reverse(words);
+    @assert(words == [string; "banana", "pear", "kiwi", "fig"]);
+    @assert(@index_of(words, "kiwi") == 2 and @index_of(words, "plum") == -1);
+    @assert(@contains(words, "fig") and not @contains(words, "Fig"));
+    @assert(@min(words) == "banana" and @max(words) == "pear");
+    [int] empty = [int];
+    @sort_by(empty, fn(int a, int b) { a > b; });
+    @reverse(empty);
+    @assert(@length(empty) == 0 and @index_of(empty, 0) == -1);
+    [float] xs = [float; 2.0, 0.0 / 0.0, -1.0];
+    @assert(@min(xs) == -1.0 and @to_string(@max(xs)) == "nan");
+    @sort_by(xs, fn(float a, float b) { a > b; });
+    @assert(xs[0] == 2.0 and xs[2] == -1.0);
+};
+
+test "break and continue" {
+    [int] odds = for int i = 0; i < 6; i = i + 1 { if i % 2 == 0 { continue; }; i; };
+    @assert(odds == [int; 1, 3, 5]);
+    [int] upto = for x in 0..10 { if x == 3 { break 100; }; x; };
+    @assert(upto == [int; 0, 1, 2, 100]);
+    [[int]] rows = 'outer: for x in 0..4 {
+        for y in 0..4 {
+            if x == 2 { continue 'outer; };
+            if x == 3 { break 'outer [int; -1]; };
+            x * y;
+        };
+    };
+    @assert(rows == [[int]; [int; 0, 0, 0, 0], [int; 0, 1, 2, 3], [int; -1]]);
+};
+
+test "loop values" {
+    @assert(loop { break 5; } == [int; 5]);
+    int i = 0;
+    [int] found = loop {
+        i = i + 1;
+        if i % 7 == 0 { break i * 10; };
+        continue;
+    };
+    @assert(found == [int; 70] and i == 7);
+    [string] evens = for x in 0..6 { if x % 2 == 1 { continue; }; break "{x}"; };
+    @assert(evens == [string; "0"]);
+    @assert(@length(for x in 0..3 { continue; }) == 0);
+};
+
+test "comprehensions" {
+    [int] xs = [int; 1, 2, 3, 4];
+    @assert([x * x for x in xs if x % 2 == 0] == [int; 4, 16]);
+    @assert([@to_string(x) for x in xs if x > 5] == [string]);
+    @assert([c for c in "abc" if c != 'b'] == [char; 'a', 'c']);
+    [[int]] m = [[int]; [int; 1, 2], [int], [int; 3]];
+    @assert([x for row in m for x in row] == [int; 1, 2, 3]);
+    @assert([[int; x] for row in m if @length(row) != 1 for x in row] == [[int]; [int; 1], [int; 2]]);
+    [(int, int)] pairs = [(int, int; x, y) for x in 0..3 for y in 0..x if y != 1];
+    @assert(pairs == [(int, int); (int, int; 1, 0), (int, int; 2, 0)]);
+    @assert(@length([x for row in [[int]] for x in row]) == 0);
+};
+
+test "ranges" {
+    int max = 9223372036854775807;
+    @assert(for i in max - 1..=max { i; } == [int; max - 1, max]);
+    @assert(for i in -max..=-max - 1 by -1 { i; } == [int; -max, -max - 1]);
+    @assert(for i in 0..max by max / 2 + 1 { i; } == [int; 0, max / 2 + 1]);
+    @assert(@contains(0..=max, max) and (0..=4) == (0..5));
+    @assert([int; 0, 1, 2][1..=max] == [int; 1, 2]);
+    @assert(@to_string(0..=10 by 2) == "0..=10 by 2" and @to_string(0..10) == "0..10");
+    @assert(@to_string(max - 1..=max) == "9223372036854775806..=9223372036854775807");
+    @assert(for i in 0..=9 by 3 { i; } == [int; 0, 3, 6, 9] and @contains(0..=9 by 3, 9));
+    @assert(for i in 4..=0 by -2 { i; } == [int; 4, 2, 0] and [int; 0, 1, 2][2..=0 by -1] == [int; 2, 1, 0]);
+    @assert(@length({range, 0..=4, 0..5}) == 1);
+};
+
+test "higher order builtins" {
+    [int] xs = [int; 1, 2, 3, 4];
+    @assert(@map(xs, fn(int x) { x * x; }) == [int; 1, 4, 9, 16]);
+    @assert(@filter(xs, fn(int x) { x % 2 == 0; }) == [int; 2, 4]);
+    @assert(@fold(xs, "", fn(string s, int x) { s + @to_string(x); }) == "1234");
+    @assert(@any(xs, fn(int x) { x > 3; }));
+    @assert(not @all(xs, fn(int x) { x > 3; }));
+    int calls = 0;
+    @assert(@any(0..1000000, fn(int x) { calls = calls + 1; x == 2; }) and calls == 3);
+    @assert(@zip(xs, [string; "a", "b"]) == [(int, string); (int, string; 1, "a"), (int, string; 2, "b")]);
+};
+
+test "maps" {
+    {int: string} m = {int: string};
+    for i in 0..100 {
+        m[i] = @to_string(i);
+    };
+    for i in 0..100 {
+        if i % 10 != 0 {
+            @assert(@remove(m, i) == @to_string(i));
+        };
+    };
+    m[5] = "five";
+    @assert(@length(m) == 11);
+    @assert(@keys(m)[:3] == [int; 0, 10, 20]);
+    @assert(m[5] == "five" and not @has(m, 6));
+};
+
+test "sets" {
+    {string} seen = {string, "b", "a", "b"};
+    @add(seen, "c");
+    @add(seen, "a");
+    @assert(@to_string(seen) == "{{b, a, c}}");
+    @assert(@has(seen, "a") and not @has(seen, "z"));
+    @assert(@remove(seen, "a") and not @remove(seen, "a") and @length(seen) == 2);
+    {int} odds = {int, 1, 3, 5};
+    {int} small = {int, 1, 2, 3};
+    @assert(@to_string(odds | small) == "{{1, 3, 5, 2}}");
+    @assert(@to_string(odds & small) == "{{1, 3}}" and @to_string(odds - small) == "{{5}}");
+    @assert(@length({int} & odds) == 0 and @length({int} | {int}) == 0);
+    {([int], string)} pairs = {([int], string), ([int], string; [int; 1], "x"), ([int], string; [int; 1], "x")};
+    @assert(@length(pairs) == 1 and @has(pairs, ([int], string; [int; 1], "x")));
+};
+
+test "floats" {
+    float half = @to_float(1) / 2.0;
+    @assert(half == 0.5);
+    @assert(@to_int(-2.5) == -2);
+    @assert(@to_string(1e20) == "1e+20" and @to_string(2.0) == "2.0");
+    @assert("{half:.3}" == "0.500");
+    [float] xs = [float; 2.5, -1.0];
+    @sort(xs);
+    @assert(xs == [float; -1.0, 2.5]);
+    float nan = 0.0 / 0.0;
+    @assert(@to_int(nan) == 0 and @to_int(1e30) == 9223372036854775807);
+    @assert(@to_int(-1e30) == -9223372036854775807 - 1);
+    [float] ys = [float; nan, 1.0, nan, -1.0];
+    @sort(ys);
+    @assert(ys == [float; -1.0, 1.0, nan, nan]);
+    @assert(@length({float, nan, 0.0, nan, -0.0}) == 2);
+};
+
+test "sized integers" {
+    @assert(@to_u8(255) + @to_u8(1) == @to_u8(0));
+    @assert(@to_int(@to_i32(-7) >> @to_i32(1)) == -4);
+    @assert(@to_u32(-1) >> @to_u32(28) == @to_u32(15));
+    @assert(@to_u64(-1) > @to_u64(1));
+    @assert((6 & 3) + (6 | 3) + (6 ^ 3) == 14);
+    [u8] bytes = [u8; @to_u8(200), @to_u8(3)];
+    @sort(bytes);
+    @assert(bytes == [u8; @to_u8(3), @to_u8(200)]);
+    @assert("{@to_i32(-1):x}" == "ffffffff");
+    @assert(256 >> 4 >> 2 == 4 and 1 << 2 << 3 == 32 and 64 / 4 / 2 == 8);
+    @assert(10 - 3 - 2 == 5 and 10 - 3 + 2 == 9);
+    @assert(@to_u8(-1.0) == @to_u8(0) and @to_u8(300.5) == @to_u8(255) and @to_u8(7.9) == @to_u8(7));
+    @assert(@to_i32(1e10) == @to_i32(2147483647) and @to_i32(-1e10) == @to_i32(-2147483648));
+    @assert(@to_u32(-0.5) == @to_u32(0) and @to_u32(4294967295.5) == @to_u32(-1) and @to_u64(-1.5) == @to_u64(0));
+    @assert(@to_u32(1e20) == @to_u32(-1) and @to_i32(0.0 / 0.0) == @to_i32(0));
+    @assert(@to_u64(1e19) == @to_u64(1000000000000000000) * @to_u64(10) and @to_u64(1e30) == @to_u64(-1));
+};
+
+test "args and exit" {
+    [string] args = @args();
+    @assert(@length(args) >= 1 and @length(args[0]) > 0);
+    @exit(0);
+    @assert(false, "@exit(0) should have ended the test");
+};
+
+test "reading input" {
+    @assert(@length(@readln()) == 0 and @length(@readln()) == 0);
+    @assert(@read_all() == "");
+    @assert(@parse_int("42") == [int; 42] and @parse_int("-7") == [int; -7]);
+    @assert(@parse_int("-9223372036854775808") == [int; -9223372036854775807 - 1]);
+    @assert(@length(@parse_int("9223372036854775808")) == 0);
+    @assert(@length(@parse_int("")) == 0 and @length(@parse_int(" 1")) == 0);
+    @assert(@length(@parse_int("1 ")) == 0 and @length(@parse_int("12a")) == 0);
+};
+
+test "files" {
+    string path = "/tmp/swindle-tests-files.txt";
+    @assert(@write_file(path, "one\n"));
+    @assert(@file_exists(path) and @read_file(path) == [string; "one\n"]);
+    @assert(@append_file(path, "two ✓\n") and @append_file(path, ""));
+    @assert(@read_file(path) == [string; "one\ntwo ✓\n"]);
+    @assert(@write_file(path, "") and @read_file(path) == [string; ""]);
+    string missing = "/tmp/swindle-tests-no-such-dir/missing.txt";
+    @assert(not @file_exists(missing) and @length(@read_file(missing)) == 0);
+    @assert(not @write_file(missing, "x") and not @append_file(missing, "x"));
+    @assert(not @file_exists(missing));
+    @assert(@length(@read_file("/tmp")) == 0);
+};
+
+test "chars" {
+    string s = "héllo, 世界";
+    @assert(@length(s) == 9 and @length(@bytes(s)) == 14);
+    @assert(s[1] == "é" and s[-2:] == "世界" and @chars(s)[7] == '世');
+    @assert(@chars("a\u{1F600}") == [char; 'a', '😀'] and @to_string('\u{1F600}') == "😀");
+    @assert(@bytes("é") == [int; 195, 169] and @length(@chars("")) == 0 and @length(@bytes("")) == 0);
+    [string] read = @read_file("examples/invalid_utf8.txt");
+    @assert(@length(read) == 1, "run the tests from the root of the repository");
+    string bad = read[0];
+    @assert(@length(bad) == 6 and @bytes(bad) == [int; 97, 255, 98, 226, 130, 99]);
+    @assert(@chars(bad) == [char; 'a', '\u{FFFD}', 'b', '\u{FFFD}', '\u{FFFD}', 'c']);
+    @assert(bad[2] == "b" and bad[5:] == "c" and bad[-1] == "c");
+};
+
+test "string builtins" {
+    @assert(@split("a,b,,c", ",") == [string; "a", "b", "", "c"]);
+    @assert(@split("", ",") == [string; ""] and @split("héj", "") == [string; "h", "é", "j"]);
+    @assert(@split("a--b--", "--") == [string; "a", "b", ""] and @length(@split("", "")) == 0);
+    @assert(@join([string; "a", "b", "c"], ", ") == "a, b, c" and @join([string], ",") == "");
+    @assert(@join(@split("x y z", " "), " ") == "x y z");
+    @assert(@trim("  \t hi there \n") == "hi there" and @trim(" \n ") == "" and @trim("") == "");
+    @assert(@find("héllo", "llo") == 2 and @find("hello", "z") == -1 and @find("abc", "") == 0);
+    @assert(@replace("a.b.c", ".", "::") == "a::b::c" and @replace("aaa", "aa", "b") == "ba");
+    @assert(@replace("", "a", "b") == "" and @replace("abc", "", "x") == "abc");
+    @assert(@starts_with("swindle", "swi") and not @starts_with("sw", "swindle") and @starts_with("x", ""));
+    @assert(@ends_with("swindle", "dle") and not @ends_with("swindle", "swi") and @ends_with("", ""));
+    @assert(@contains("swindle", "wind") and not @contains("swindle", "Wind") and @contains("", ""));
+    @assert(@to_upper("héllo, World 1") == "HéLLO, WORLD 1" and @to_lower("ÀBC") == "Àbc");
+};
+
+test "to_string and parse_bool" {
+    @assert(@to_string(-42) == "-42" and @to_string(true) == "true" and @to_string(unit) == "()");
+    @assert(@to_string('x') == "x" and @to_string("") == "" and @to_string(0.1) == "0.1");
+    @assert(@to_string(@to_u64(-1)) == "18446744073709551615" and @to_string(@to_i32(-5)) == "-5");
+    @assert(@to_string([[int]; [int; 1], [int]]) == "[[1], []]" and @to_string([string]) == "[]");
+    @assert(@to_string((int, string, bool; 1, "a", false)) == "(1, a, false)");
+    {string: [int]} m = {string: [int]};
+    m["k"] = [int; 1, 2];
+    @assert(@to_string(m) == "{{k: [1, 2]}}" and @to_string({int: int}) == "{{}}");
+    @assert("count: " + @to_string(3) == "count: 3");
+    @assert(@parse_bool("true") == [bool; true] and @parse_bool("false") == [bool; false]);
+    @assert(@length(@parse_bool("True")) == 0 and @length(@parse_bool("")) == 0);
+    @assert(@length(@parse_bool(" true")) == 0 and @length(@parse_bool("truex")) == 0);
+};
+
+test "format specs" {
+    int n = 42;
+    @assert(@format("{:>5}|{:<5}|{:^6}|", n, n, n) == "   42|42   |  42  |");
+    @assert(@format("{:*^7}|{:5}|{:>3}|", "ab", "ab", "long") == "**ab***|ab   |long|");
+    @assert(@format("{:08}|{:08}|{:03}", -42, 42, 12345) == "-0000042|00000042|12345");
+    @assert(@format("{:x}|{:X}|{:o}|{:b}", 255, 255, 8, 5) == "ff|FF|10|101");
+    @assert(@format("{:08x}|{:x}", 255, @to_u64(-1)) == "000000ff|ffffffffffffffff");
+    @assert(@format("{:.2}|{:.0}|{:8.3}|", 3.14159, 2.5, -1.0) == "3.14|2|  -1.000|");
+    @assert(@format("{:.3}|{:.10}|", "swindle", "hé") == "swi|hé|");
+    @assert(@format("{:?}|{:?}|{}", "a\"b", 'c', "plain") == "\"a\\\"b\"|'c'|plain");
+    @assert(@format("{:?}", [string; "x"]) == "[\"x\"]" and @format("{}", [string; "x"]) == "[x]");
+    @assert(@format("{:>4}|{:<3}|", '☃', true) == "   ☃|true|");
+    @assert("{n:>4}|{n:b}" == "  42|101010" and @format("no placeholders") == "no placeholders");
+};
+
+test "concatenation and repetition" {
+    @assert("ab" + "" + "cd" == "abcd" and "-" * 3 == "---");
+    @assert("ab" * 0 == "" and "ab" * -2 == "" and "" * 5 == "");
+    [int] xs = [int; 1, 2];
+    [int] ys = xs + [int; 3];
+    @push(xs, 9);
+    @assert(ys == [int; 1, 2, 3] and xs == [int; 1, 2, 9]);
+    @assert([int] + [int] == [int] and xs + [int] == xs);
+    @assert([int; 0] * 3 == [int; 0, 0, 0] and @length(xs * 0) == 0 and @length(xs * -1) == 0);
+    [[int]] grid = [[int]; [int; 0]] * 2;
+    grid[0][0] = 1;
+    @assert(grid == [[int]; [int; 1], [int; 1]]);
+    @assert([string; "a"] * 2 + [string; "b"] == [string; "a", "a", "b"]);
+};
+
+test "ordering" {
+    @assert("" < "a" and "abc" < "abd" and "ab" < "abc" and "b" > "abc");
+    @assert("Z" < "a" and "z" < "é" and "é" < "世" and "a" <= "a" and "a" >= "a");
+    @assert([int] < [int; 0] and [int; 1, 2] < [int; 1, 2, 0] and [int; 2] > [int; 1, 9]);
+    @assert([string; "a", "b"] <= [string; "a", "b"] and [[int]; [int; 1]] < [[int]; [int; 1, 0]]);
+    @assert((int, string; 1, "b") < (int, string; 2, "a") and (int, string; 1, "a") < (int, string; 1, "b"));
+    float nan = 0.0 / 0.0;
+    @assert(not (nan < 1.0) and not (nan > 1.0) and not (nan == nan) and nan != nan);
+    @assert([float; nan] > [float; 1.0] and [float; nan] == [float; nan] and [float; 1.0, nan] < [float; 2.0]);
+    @assert((float, int; nan, 1) < (float, int; nan, 2) and (float, int; 1.0, 9) < (float, int; nan, 0));
+};
+
+test "tuples and destructuring" {
+    (int, string) pair = (int, string; 1, "one");
+    (int n, string s) = pair;
+    @assert(n == 1 and s == "one" and pair == (int, string; 1, "one"));
+    (float, bool) flat = (float, bool; 0.5, true);
+    (float f, bool b) = flat;
+    @assert(f == 0.5 and b and @to_string(flat) == "(0.5, true)");
+    int x = 1;
+    int y = 2;
+    (int a, int c) = (int, int; y, x);
+    @assert(a == 2 and c == 1);
+    ((int, int), [string]) nested = ((int, int), [string]; (int, int; 3, 4), [string; "a"]);
+    ((int, int) inner, [string] names) = nested;
+    (int p, int q) = inner;
+    @push(names, "b");
+    @assert(p + q == 7 and nested == ((int, int), [string]; (int, int; 3, 4), [string; "a", "b"]));
+    [string] seen = for (i, word) in @enumerate([string; "a", "b"]) { "{i}{word}"; };
+    @assert(seen == [string; "0a", "1b"]);
+    {(int, int): string} grid = {(int, int): string};
+    grid[(int, int; 0, 1)] = "x";
+    @assert(grid[(int, int; 0, 1)] == "x" and not @has(grid, (int, int; 1, 0)));
+    (int, string) copy = pair;
+    @assert(copy == pair and (int, string; 1, "a") != (int, string; 1, "b"));
+};
@@ -1,61 +1,569 @@
 #include <stdio.h>
 #include <stdint.h>
 #include <stdbool.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdarg.h>
+#include <assert.h>
+#include <math.h>
+#include <sys/types.h>
+#include <unistd.h>
 
 #include "rc.h"
 #include "strings.h"
 #include "lists.h"
+#include "maps.h"
+#include "tuples.h"
+#include "ranges.h"
 
-void print_int(int64_t i) {
-    printf("%ld", i);
+static int sw_argc = 0;
+static char **sw_argv = NULL;
+
+void init_args(int argc, char **argv) {
+    sw_argc = argc;
+    sw_argv = argv;
 }
 
-void print_bool(bool b) {
-    printf("%s", b ? "true" : "false");
+/*
+ * Creates a list of the command line arguments, including the program name
+ */
+void args_(RC *dest) {
+    rc_list(dest, SW_STRING, 0);
+    for(int i = 0; i < sw_argc; i++) {
+        RC arg;
+        rc_string(&arg, sw_argv[i]);
+        push_(dest, &arg);
+    }
 }
 
-void print_unit(__attribute__((unused)) bool _u) {
-    printf("()");
+/*
+ * The fprint_ functions write the default rendering of a value to a stream,
+ * which is shared by @write and @to_string
+ */
+
+void fprint_int(FILE *file, int64_t i) {
+    fprintf(file, "%ld", i);
 }
 
-void print_string(RC *s) {
+void fprint_u64(FILE *file, int64_t i) {
+    fprintf(file, "%lu", (uint64_t)i);
+}
+
+/*
+ * The shortest rendering which reads back as the same float, with a . or an exponent so it
+ * doesn't look like an int. Exponents are only used for very large or small magnitudes.
+ */
+void fprint_float(FILE *file, double f) {
+    if(!isfinite(f)) {
+        fprintf(file, "%g", f);
+        return;
+    }
+
+    char buffer[32];
+    int digits = 0;
+    for(; digits < 17; digits++) {
+        snprintf(buffer, sizeof(buffer), "%.*e", digits, f);
+        if(strtod(buffer, NULL) == f)
+            break;
+    }
+
+    int exponent = atoi(strchr(buffer, 'e') + 1);
+    if(exponent < -5 || exponent >= 16) {
+        fprintf(file, "%s", buffer);
+    } else if(digits > exponent) {
+        fprintf(file, "%.*f", digits - exponent, f);
+    } else {
+        fprintf(file, "%.0f.0", f);
+    }
+}
+
+void fprint_bool(FILE *file, bool b) {
+    fprintf(file, "%s", b ? "true" : "false");
+}
+
+void fprint_unit(FILE *file, __attribute__((unused)) bool _u) {
+    fprintf(file, "()");
+}
+
+void fprint_char(FILE *file, int32_t c) {
+    char buffer[5];
+    buffer[utf8_encode(c, buffer)] = '\0';
+    fprintf(file, "%s", buffer);
+}
+
+void fprint_string(FILE *file, RC *s) {
     String *str = (String *)s->reference;
-    printf("%s", str->string);
+    fprintf(file, "%s", str->string);
     destroy_noref(s);
 }
 
-void print_list(RC *l) {
-    alloc(l); // index_list will destroy a list if no reference is held, so we hold one...
+/*
+ * Writes c escaped the same way as a literal in swindle source, without the quotes
+ */
+void fprint_escaped(FILE *file, int32_t c, char quote) {
+    switch(c) {
+        case '\n': fprintf(file, "\\n"); break;
+        case '\t': fprintf(file, "\\t"); break;
+        case '\r': fprintf(file, "\\r"); break;
+        case '\\': fprintf(file, "\\\\"); break;
+        default:
+            if(c == quote)
+                fprintf(file, "\\%c", quote);
+            else if(c < 0x20 || c == 0x7F)
+                fprintf(file, "\\u{%x}", c);
+            else
+                fprint_char(file, c);
+    }
+}
+
+void fprint_debug_char(FILE *file, int32_t c) {
+    fprintf(file, "'");
+    fprint_escaped(file, c, '\'');
+    fprintf(file, "'");
+}
+
+void fprint_debug_string(FILE *file, RC *s) {
+    String *str = (String *)s->reference;
+    fprintf(file, "\"");
+    size_t width;
+    for(size_t i = 0; i < str->length; i += width)
+        fprint_escaped(file, utf8_decode(str->string + i, &width), '"');
+    fprintf(file, "\"");
+    destroy_noref(s);
+}
+
+// lists, maps, sets and tuples can contain each other
+void fprint_list(FILE *file, RC *l, bool debug);
+void fprint_map(FILE *file, RC *m, bool debug);
+void fprint_set(FILE *file, RC *s, bool debug);
+void fprint_tuple(FILE *file, RC *t, bool debug);
+void fprint_range(FILE *file, RC *r);
+
+/*
+ * With debug set, strings and chars are quoted, including inside lists, maps, sets and tuples
+ */
+void fprint_item(FILE *file, ItemType item_type, ListItem item, bool debug) {
+    switch(item_type) {
+        case SW_INT:
+        case SW_U8:
+        case SW_I32:
+        case SW_U32:
+            fprint_int(file, item.n);
+            break;
+        case SW_U64:
+            fprint_u64(file, item.n);
+            break;
+        case SW_BOOL:
+            fprint_bool(file, item.b);
+            break;
+        case SW_UNIT:
+            fprint_unit(file, item.u);
+            break;
+        case SW_CHAR:
+            if(debug)
+                fprint_debug_char(file, item.c);
+            else
+                fprint_char(file, item.c);
+            break;
+        case SW_STRING:
+            if(debug)
+                fprint_debug_string(file, as_rc(item));
+            else
+                fprint_string(file, as_rc(item));
+            break;
+        case SW_LIST:
+            fprint_list(file, as_rc(item), debug);
+            break;
+        case SW_MAP:
+            fprint_map(file, as_rc(item), debug);
+            break;
+        case SW_SET:
+            fprint_set(file, as_rc(item), debug);
+            break;
+        case SW_TUPLE:
+            fprint_tuple(file, as_rc(item), debug);
+            break;
+        case SW_RANGE:
+            fprint_range(file, as_rc(item));
+            break;
+        case SW_FLOAT:
+            fprint_float(file, item.f);
+            break;
+    }
+}
+
+void fprint_list(FILE *file, RC *l, bool debug) {
     List *list = (List *)l->reference;
 
-    printf("[");
-    for(int64_t i = 0; i < (int64_t)list->length; i++) {
-        ListItem item = index_list(l, i);
-        switch(list->item_type) {
-            case SW_INT:
-                print_int(item.n);
-                break;
-            case SW_BOOL:
-                print_bool(item.b);
-                break;
-            case SW_UNIT:
-                print_unit(item.u);
-                break;
-            case SW_STRING:
-                print_string(as_rc(item));
-                break;
-            case SW_LIST:
-                print_list(as_rc(item));
-                break;
-        }
-        if((size_t)i != list->length - 1)
-            printf(", ");
+    fprintf(file, "[");
+    for(size_t i = 0; i < list->length; i++) {
+        fprint_item(file, list->item_type, get_item(list, i), debug);
+        if(i != list->length - 1)
+            fprintf(file, ", ");
+    }
+    fprintf(file, "]");
+
+    destroy_noref(l);
+}
+
+/*
+ * Maps are written like {1: "one", 2: "two"}, in the order the keys were added
+ */
+void fprint_map(FILE *file, RC *m, bool debug) {
+    Map *map = (Map *)m->reference;
+    compact_(map);
+
+    fprintf(file, "{");
+    for(size_t i = 0; i < map->keys->length; i++) {
+        fprint_item(file, map->keys->item_type, get_item(map->keys, i), debug);
+        fprintf(file, ": ");
+        fprint_item(file, map->values->item_type, get_item(map->values, i), debug);
+        if(i != map->keys->length - 1)
+            fprintf(file, ", ");
     }
-    printf("]");
+    fprintf(file, "}");
 
-    drop(l); // and then drop it
+    destroy_noref(m);
+}
+
+/*
+ * Sets are written like {1, 2, 3}, in the order the items were added
+ */
+void fprint_set(FILE *file, RC *s, bool debug) {
+    Map *set = (Map *)s->reference;
+    compact_(set);
+
+    fprintf(file, "{");
+    for(size_t i = 0; i < set->keys->length; i++) {
+        fprint_item(file, set->keys->item_type, get_item(set->keys, i), debug);
+        if(i != set->keys->length - 1)
+            fprintf(file, ", ");
+    }
+    fprintf(file, "}");
+
+    destroy_noref(s);
+}
+
+void fprint_tuple(FILE *file, RC *t, bool debug) {
+    Tuple *tuple = (Tuple *)t->reference;
+
+    fprintf(file, "(");
+    for(size_t i = 0; i < tuple->length; i++) {
+        fprint_item(file, tuple->item_types[i], get_tuple_item(tuple, i), debug);
+        if(i != tuple->length - 1)
+            fprintf(file, ", ");
+    }
+    fprintf(file, ")");
+
+    destroy_noref(t);
+}
+
+/*
+ * The step is left out when it's 1, and the stop is written the way the range was
+ */
+void fprint_range(FILE *file, RC *r) {
+    Range *range = (Range *)r->reference;
+
+    fprintf(file, range->inclusive ? "%ld..=%ld" : "%ld..%ld", range->start, range->stop);
+    if(range->step != 1)
+        fprintf(file, " by %ld", range->step);
+
+    destroy_noref(r);
+}
+
+void print_int(int64_t i) {
+    fprint_int(stdout, i);
+}
+
+void print_u64(int64_t i) {
+    fprint_u64(stdout, i);
+}
+
+void print_float(double f) {
+    fprint_float(stdout, f);
+}
+
+void print_bool(bool b) {
+    fprint_bool(stdout, b);
+}
+
+void print_unit(bool u) {
+    fprint_unit(stdout, u);
+}
+
+void print_char(int32_t c) {
+    fprint_char(stdout, c);
+}
+
+void print_string(RC *s) {
+    fprint_string(stdout, s);
+}
+
+void print_list(RC *l) {
+    fprint_list(stdout, l, false);
+}
+
+void print_map(RC *m) {
+    fprint_map(stdout, m, false);
+}
+
+void print_set(RC *s) {
+    fprint_set(stdout, s, false);
+}
+
+void print_tuple(RC *t) {
+    fprint_tuple(stdout, t, false);
+}
+
+void print_range(RC *r) {
+    fprint_range(stdout, r);
+}
+
+/*
+ * The to_string_ functions render a value the same way as @write, but into a new string
+ */
+
+FILE *open_string(char **string, size_t *length) {
+    FILE *file = open_memstream(string, length);
+    assert(file != NULL);
+    return file;
+}
+
+void close_string(RC *dest, FILE *file, char **string, size_t *length) {
+    fclose(file);
+    rc_string_owned(dest, *string, *length);
+}
+
+/*
+ * Renders an item into dest, with fprint_item
+ */
+void render_(RC *dest, ItemType item_type, ListItem item, bool debug) {
+    char *string;
+    size_t length;
+    FILE *file = open_string(&string, &length);
+    fprint_item(file, item_type, item, debug);
+    close_string(dest, file, &string, &length);
+}
+
+void to_string_int(RC *dest, int64_t i) {
+    render_(dest, SW_INT, (ListItem){ .n = i }, false);
+}
+
+void to_string_u64(RC *dest, int64_t i) {
+    render_(dest, SW_U64, (ListItem){ .n = i }, false);
+}
+
+void to_string_float(RC *dest, double f) {
+    render_(dest, SW_FLOAT, (ListItem){ .f = f }, false);
+}
+
+void to_string_bool(RC *dest, bool b) {
+    render_(dest, SW_BOOL, (ListItem){ .b = b }, false);
+}
+
+void to_string_unit(RC *dest, bool u) {
+    render_(dest, SW_UNIT, (ListItem){ .u = u }, false);
+}
+
+void to_string_char(RC *dest, int32_t c) {
+    render_(dest, SW_CHAR, (ListItem){ .c = c }, false);
+}
+
+void to_string_string(RC *dest, RC *s) {
+    render_(dest, SW_STRING, (ListItem){ .rc = s }, false);
+}
+
+void to_string_list(RC *dest, RC *l) {
+    render_(dest, SW_LIST, (ListItem){ .rc = l }, false);
+}
+
+void to_string_map(RC *dest, RC *m) {
+    render_(dest, SW_MAP, (ListItem){ .rc = m }, false);
+}
+
+void to_string_set(RC *dest, RC *s) {
+    render_(dest, SW_SET, (ListItem){ .rc = s }, false);
+}
+
+void to_string_tuple(RC *dest, RC *t) {
+    render_(dest, SW_TUPLE, (ListItem){ .rc = t }, false);
+}
+
+void to_string_range(RC *dest, RC *r) {
+    render_(dest, SW_RANGE, (ListItem){ .rc = r }, false);
+}
+
+/*
+ * The debug_ functions are the same as to_string_, but quote strings and chars
+ */
+
+void debug_char(RC *dest, int32_t c) {
+    render_(dest, SW_CHAR, (ListItem){ .c = c }, true);
+}
+
+void debug_string(RC *dest, RC *s) {
+    render_(dest, SW_STRING, (ListItem){ .rc = s }, true);
+}
+
+void debug_list(RC *dest, RC *l) {
+    render_(dest, SW_LIST, (ListItem){ .rc = l }, true);
+}
+
+void debug_map(RC *dest, RC *m) {
+    render_(dest, SW_MAP, (ListItem){ .rc = m }, true);
+}
+
+void debug_set(RC *dest, RC *s) {
+    render_(dest, SW_SET, (ListItem){ .rc = s }, true);
+}
+
+void debug_tuple(RC *dest, RC *t) {
+    render_(dest, SW_TUPLE, (ListItem){ .rc = t }, true);
+}
+
+/*
+ * Renders n in the given radix, treating negative numbers as two's complement
+ */
+void to_string_radix(RC *dest, int64_t n, int64_t radix, bool upper) {
+    const char *digits = upper ? "0123456789ABCDEF" : "0123456789abcdef";
+    char buffer[65];
+    size_t i = sizeof(buffer);
+    uint64_t u = (uint64_t)n;
+    do {
+        buffer[--i] = digits[u % radix];
+        u /= radix;
+    } while(u > 0);
+
+    size_t length = sizeof(buffer) - i;
+    char *string = malloc(length + 1);
+    memcpy(string, buffer + i, length);
+    string[length] = '\0';
+    rc_string_owned(dest, string, length);
+}
+
+/*
+ * Renders f with exactly precision digits after the decimal point
+ */
+void to_string_fixed(RC *dest, double f, int64_t precision) {
+    char *string;
+    size_t length;
+    FILE *file = open_string(&string, &length);
+    fprintf(file, "%.*f", (int)precision, f);
+    close_string(dest, file, &string, &length);
 }
 
 void print_line() {
     printf("\n");
 }
+
+/*
+ * Reads a line from stdin, without the trailing newline
+ * dest is an empty list at EOF, otherwise a list of just the line
+ */
+void readln_(RC *dest) {
+    fflush(stdout); // so that prompts show up before we wait for input
+
+    rc_list(dest, SW_STRING, 0);
+    char *line = NULL;
+    size_t capacity = 0;
+    ssize_t length = getline(&line, &capacity, stdin);
+    if(length < 0) {
+        free(line);
+        return;
+    }
+
+    if(length > 0 && line[length - 1] == '\n')
+        line[--length] = '\0';
+    RC rc;
+    rc_string_owned(&rc, line, length);
+    push_(dest, &rc);
+}
+
+/*
+ * Reads the rest of a stream into a malloced string
+ */
+char *read_stream(FILE *file, size_t *length) {
+    size_t capacity = 256;
+    char *contents = malloc(capacity);
+    size_t count;
+    *length = 0;
+    while((count = fread(contents + *length, 1, capacity - *length - 1, file)) > 0) {
+        *length += count;
+        if(*length + 1 == capacity) {
+            capacity *= 2;
+            contents = realloc(contents, capacity);
+        }
+    }
+    contents[*length] = '\0';
+    return contents;
+}
+
+/*
+ * Reads the rest of stdin
+ */
+void read_all_(RC *dest) {
+    fflush(stdout);
+
+    size_t length;
+    char *contents = read_stream(stdin, &length);
+    rc_string_owned(dest, contents, length);
+}
+
+/*
+ * dest is an empty list if the file can't be read, otherwise a list of just its contents
+ */
+void read_file(RC *dest, RC *path) {
+    String *path_str = (String *)path->reference;
+    FILE *file = fopen(path_str->string, "rb");
+    destroy_noref(path);
+
+    rc_list(dest, SW_STRING, 0);
+    if(file == NULL) return;
+
+    size_t length;
+    char *contents = read_stream(file, &length);
+    bool failed = ferror(file);
+    fclose(file);
+    if(failed) {
+        free(contents);
+        return;
+    }
+
+    RC rc;
+    rc_string_owned(&rc, contents, length);
+    push_(dest, &rc);
+}
+
+bool write_to_file(RC *path, RC *contents, char *mode) {
+    String *path_str = (String *)path->reference,
+           *contents_str = (String *)contents->reference;
+    FILE *file = fopen(path_str->string, mode);
+    bool success = file != NULL;
+    if(success) {
+        success = fwrite(contents_str->string, 1, contents_str->length, file)
+            == contents_str->length;
+        success &= fclose(file) == 0;
+    }
+
+    destroy_noref(path);
+    destroy_noref(contents);
+    return success;
+}
+
+/*
+ * returns whether the write succeeded
+ */
+bool write_file(RC *path, RC *contents) {
+    return write_to_file(path, contents, "wb");
+}
+
+bool append_file(RC *path, RC *contents) {
+    return write_to_file(path, contents, "ab");
+}
+
+bool file_exists(RC *path) {
+    String *path_str = (String *)path->reference;
+    bool exists = access(path_str->string, F_OK) == 0;
+    destroy_noref(path);
+    return exists;
+}
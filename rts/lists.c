@@ -3,23 +3,80 @@
 #include <stdint.h>
 #include <stdarg.h>
 #include <assert.h>
+#include <string.h>
+#include <stdio.h>
+#include <math.h>
 
 #include "rc.h"
 #include "lists.h"
 #include "strings.h"
+#include "tuples.h"
+#include "ranges.h"
+#include "panic.h"
 
 #define GROW_CAPACITY(capacity) (2 * (capacity))
 #define MIN_CAPACITY 8
 #define MAX(A,B) ((A) > (B) ? (A) : (B))
 
+/*
+ * Negative indices count from the end
+ */
+int64_t normalize_index(int64_t idx, size_t length) {
+    return idx < 0 ? idx + (int64_t)length : idx;
+}
+
+/*
+ * Panics the same way as @panic, for an index the program gave which is out of range
+ */
+void index_out_of_range_(int64_t line, int64_t column, int64_t idx, size_t length) {
+    char message[80];
+    snprintf(message, sizeof(message), "index %ld out of range for length %zu", idx, length);
+    panic_message_(line, column, message);
+}
+
+/*
+ * Normalizes an index the program gave, panicking if it's out of range
+ */
+int64_t check_index_(int64_t line, int64_t column, int64_t idx, size_t length) {
+    int64_t normalized = normalize_index(idx, length);
+    if(normalized < 0 || (size_t)normalized >= length)
+        index_out_of_range_(line, column, idx, length);
+    return normalized;
+}
+
+/*
+ * Panics the same way as @panic if a builtin which needs an item, like @pop, gets an empty list
+ */
+void check_nonempty_(int64_t line, int64_t column, RC *l, char *builtin) {
+    if(((List *)l->reference)->length > 0)
+        return;
+    char message[80];
+    snprintf(message, sizeof(message), "%s of an empty list", builtin);
+    panic_message_(line, column, message);
+}
+
+/*
+ * Normalizes the bounds of the slice [low, high), then clamps them to [0, length]
+ */
+void clamp_slice(int64_t *low, int64_t *high, size_t length) {
+    *low = normalize_index(*low, length);
+    *high = normalize_index(*high, length);
+    *low = *low < 0 ? 0 : (*low > (int64_t)length ? (int64_t)length : *low);
+    *high = *high < *low ? *low : (*high > (int64_t)length ? (int64_t)length : *high);
+}
+
+/*
+ * Whether items of this type are reference counted, and stored as an RC
+ */
+bool is_rc_item(ItemType item_type) {
+    return item_type == SW_STRING || item_type == SW_LIST || item_type == SW_MAP
+        || item_type == SW_SET || item_type == SW_TUPLE || item_type == SW_RANGE;
+}
+
 void destroy_list(List *list) {
-    switch(list->item_type) {
-        case SW_STRING:
-        case SW_LIST:
-            for(size_t i = 0; i < list->length; i++)
-                drop(((RC *)list->items) + i);
-            break;
-        default: break;
+    if(is_rc_item(list->item_type)) {
+        for(size_t i = 0; i < list->length; i++)
+            drop(((RC *)list->items) + i);
     }
     free(list->items);
     free(list);
@@ -28,10 +85,20 @@ void destroy_list(List *list) {
 size_t item_size(ItemType item_type) {
     switch(item_type){
         case SW_INT: return sizeof(int64_t);
-        case SW_BOOL: return sizeof(int);
+        case SW_BOOL: return sizeof(bool);
         case SW_UNIT: return 0;
+        case SW_CHAR: return sizeof(int32_t);
+        case SW_FLOAT: return sizeof(double);
+        case SW_U8: return sizeof(uint8_t);
+        case SW_I32: return sizeof(int32_t);
+        case SW_U32: return sizeof(uint32_t);
+        case SW_U64: return sizeof(uint64_t);
         case SW_STRING:
-        case SW_LIST: return sizeof(RC);
+        case SW_LIST:
+        case SW_MAP:
+        case SW_SET:
+        case SW_TUPLE:
+        case SW_RANGE: return sizeof(RC);
     }
 }
 
@@ -56,8 +123,30 @@ void rc_list(RC *rc, ItemType item_type, size_t count, ...) {
                 break;
             case SW_UNIT:
                 break;
+            case SW_CHAR:
+                ((int32_t *)list->items)[i] = va_arg(ap, int32_t);
+                break;
+            case SW_FLOAT:
+                ((double *)list->items)[i] = va_arg(ap, double);
+                break;
+            case SW_U8:
+                ((uint8_t *)list->items)[i] = va_arg(ap, int64_t);
+                break;
+            case SW_I32:
+                ((int32_t *)list->items)[i] = va_arg(ap, int64_t);
+                break;
+            case SW_U32:
+                ((uint32_t *)list->items)[i] = va_arg(ap, int64_t);
+                break;
+            case SW_U64:
+                ((uint64_t *)list->items)[i] = va_arg(ap, int64_t);
+                break;
             case SW_STRING:
             case SW_LIST:
+            case SW_MAP:
+            case SW_SET:
+            case SW_TUPLE:
+            case SW_RANGE:
                 ((RC *)list->items)[i] = *alloc(va_arg(ap, RC *));
                 break;
         }
@@ -67,23 +156,146 @@ void rc_list(RC *rc, ItemType item_type, size_t count, ...) {
     new(rc, list, (Destructor) destroy_list);
 }
 
-ListItem index_list(RC *l, int64_t idx) {
-    List *list = (List *)l->reference;
-    assert(idx >= 0 && (size_t)idx < list->length);
-
+/*
+ * The item at idx, an RC item points into the list
+ */
+ListItem get_item(List *list, size_t idx) {
     ListItem item;
     switch(list->item_type) {
         case SW_INT: item.n = ((int64_t *)list->items)[idx]; break;
         case SW_BOOL: item.b = ((bool *)list->items)[idx]; break;
-        case SW_UNIT: item.u = 0;
+        case SW_CHAR: item.c = ((int32_t *)list->items)[idx]; break;
+        case SW_FLOAT: item.f = ((double *)list->items)[idx]; break;
+        case SW_U8: item.n = ((uint8_t *)list->items)[idx]; break;
+        case SW_I32: item.n = ((int32_t *)list->items)[idx]; break;
+        case SW_U32: item.n = ((uint32_t *)list->items)[idx]; break;
+        case SW_U64: item.n = ((uint64_t *)list->items)[idx]; break;
+        case SW_UNIT: item.u = 0; break;
         case SW_STRING:
-        case SW_LIST: item.rc = ((RC *)list->items) + idx; break;
+        case SW_LIST:
+        case SW_MAP:
+        case SW_SET:
+        case SW_TUPLE:
+        case SW_RANGE: item.rc = ((RC *)list->items) + idx; break;
     }
+    return item;
+}
+
+ListItem index_list(RC *l, int64_t line, int64_t column, int64_t idx) {
+    List *list = (List *)l->reference;
+    idx = check_index_(line, column, idx, list->length);
+    return index_list_(l, idx);
+}
+
+/*
+ * Like index_list, for an index which the compiler or RTS already knows is in range
+ */
+ListItem index_list_(RC *l, int64_t idx) {
+    List *list = (List *)l->reference;
+    assert(idx >= 0 && (size_t)idx < list->length);
+
+    ListItem item = get_item(list, idx);
     destroy_noref(l);
 
     return item;
 }
 
+/*
+ * Slices [low, high) the same way as index_string2
+ */
+void slice_list(RC *dest, RC *src, int64_t low, int64_t high) {
+    List *src_list = (List *)src->reference;
+    clamp_slice(&low, &high, src_list->length);
+
+    size_t size = item_size(src_list->item_type);
+    List *list = malloc(sizeof(List));
+    list->item_type = src_list->item_type;
+    list->length = high - low;
+    list->capacity = list->item_type == SW_UNIT ? 0 : MAX(list->length, MIN_CAPACITY);
+    list->items = malloc(size * list->capacity);
+    memcpy(list->items, (char *)src_list->items + low * size, list->length * size);
+    if(is_rc_item(list->item_type)) {
+        for(size_t i = 0; i < list->length; i++)
+            alloc(((RC *)list->items) + i);
+    }
+
+    destroy_noref(src);
+    new(dest, list, (Destructor) destroy_list);
+}
+
+/*
+ * The items at each index of the range r which is in bounds, in the order r goes
+ */
+void slice_list_range(RC *dest, RC *src, RC *r) {
+    List *src_list = (List *)src->reference;
+    Range range = *(Range *)r->reference;
+    size_t count = clamp_range_(&range, src_list->length),
+           size = item_size(src_list->item_type);
+    List *list = empty_list(src_list->item_type, count);
+    for(size_t i = 0; i < count; i++) {
+        int64_t idx = range.start + (int64_t)i * range.step;
+        memcpy((char *)list->items + i * size, (char *)src_list->items + idx * size, size);
+        if(is_rc_item(list->item_type))
+            alloc(((RC *)list->items) + i);
+    }
+    list->length = count;
+
+    destroy_noref(src);
+    destroy_noref(r);
+    new(dest, list, (Destructor) destroy_list);
+}
+
+/*
+ * Creates a list with capacity for length items of the given type
+ */
+List *empty_list(ItemType item_type, size_t length) {
+    List *list = malloc(sizeof(List));
+    list->item_type = item_type;
+    list->length = 0;
+    list->capacity = item_type == SW_UNIT ? 0 : MAX(length, MIN_CAPACITY);
+    list->items = malloc(item_size(item_type) * list->capacity);
+    return list;
+}
+
+/*
+ * Copies the items of src onto the end of list, which must have room for them
+ */
+void copy_items(List *list, List *src) {
+    size_t size = item_size(list->item_type);
+    memcpy((char *)list->items + list->length * size, src->items, src->length * size);
+    if(is_rc_item(list->item_type)) {
+        for(size_t i = 0; i < src->length; i++)
+            alloc(((RC *)list->items) + list->length + i);
+    }
+    list->length += src->length;
+}
+
+void concat_list(RC *dest, RC *l1, RC *l2) {
+    List *list1 = (List *)l1->reference,
+         *list2 = (List *)l2->reference,
+         *list = empty_list(list1->item_type, list1->length + list2->length);
+    copy_items(list, list1);
+    copy_items(list, list2);
+
+    destroy_noref(l1);
+    destroy_noref(l2);
+    new(dest, list, (Destructor) destroy_list);
+}
+
+/*
+ * Repeats the items of src n times, a negative n gives an empty list
+ */
+void repeat_list(RC *dest, RC *src, int64_t n) {
+    List *src_list = (List *)src->reference;
+    n = n < 0 ? 0 : n;
+    List *list = empty_list(src_list->item_type, src_list->length * n);
+    for(int64_t i = 0; i < n; i++)
+        copy_items(list, src_list);
+
+    destroy_noref(src);
+    new(dest, list, (Destructor) destroy_list);
+}
+
 int64_t length_list(RC *l) {
     List *list = (List *)l->reference;
     int64_t length = (int64_t)list->length;
@@ -103,34 +315,258 @@ bool as_unit(ListItem item) {
     return item.u;
 }
 
+int32_t as_char(ListItem item) {
+    return item.c;
+}
+
+double as_float(ListItem item) {
+    return item.f;
+}
+
 RC *as_rc(ListItem item) {
     return item.rc;
 }
 
-// varargs is a hack to accept any type as input
-void push_(RC *l, ...) {
+/*
+ * Reads an item of the given type from ap into slot, in the same format as in a list
+ * RC items are copied without incrementing their count
+ */
+void read_item_(ItemType item_type, va_list *ap, void *slot) {
+    switch(item_type) {
+        case SW_INT: *(int64_t *)slot = va_arg(*ap, int64_t); break;
+        case SW_BOOL: *(bool *)slot = va_arg(*ap, int); break;
+        case SW_UNIT: *(bool *)slot = va_arg(*ap, int); break;
+        case SW_CHAR: *(int32_t *)slot = va_arg(*ap, int32_t); break;
+        case SW_FLOAT: *(double *)slot = va_arg(*ap, double); break;
+        case SW_U8: *(uint8_t *)slot = va_arg(*ap, int64_t); break;
+        case SW_I32: *(int32_t *)slot = va_arg(*ap, int64_t); break;
+        case SW_U32: *(uint32_t *)slot = va_arg(*ap, int64_t); break;
+        case SW_U64: *(uint64_t *)slot = va_arg(*ap, int64_t); break;
+        case SW_STRING:
+        case SW_LIST:
+        case SW_MAP:
+        case SW_SET:
+        case SW_TUPLE:
+        case SW_RANGE: *(RC *)slot = *va_arg(*ap, RC *); break;
+    }
+}
+
+/*
+ * Inserts the item in ap before idx, which can be the length of the list
+ * the item is read before the list grows, since an RC item might point into it
+ */
+void insert_varargs_(RC *l, int64_t idx, va_list *ap) {
     List *list = (List *)l->reference;
 
-    if(list->length == list->capacity) {
+    RC item; // big enough for any type of item
+    read_item_(list->item_type, ap, &item);
+    if(is_rc_item(list->item_type))
+        alloc(&item);
+    insert_slot_(list, idx, &item);
+}
+
+/*
+ * Inserts a copy of the item in slot before idx, growing the list if it's full
+ * an RC item is copied without incrementing its count
+ */
+void insert_slot_(List *list, size_t idx, void *item) {
+    size_t size = item_size(list->item_type);
+    if(list->length == list->capacity && list->item_type != SW_UNIT) {
         list->capacity = GROW_CAPACITY(list->capacity);
-        list->items = realloc(list->items, item_size(list->item_type) * list->capacity);
+        list->items = realloc(list->items, size * list->capacity);
     }
-    if(list->item_type == SW_STRING || list->item_type == SW_LIST)
-        uninit(((RC *)list->items) + list->length);
+    char *slot = (char *)list->items + idx * size;
+    memmove(slot + size, slot, (list->length - idx) * size);
+    memcpy(slot, item, size);
     list->length += 1;
+}
+
+/*
+ * Removes the item at idx without dropping it
+ */
+void remove_slot_(List *list, size_t idx) {
+    size_t size = item_size(list->item_type);
+    char *slot = (char *)list->items + idx * size;
+    memmove(slot, slot + size, (list->length - idx - 1) * size);
+    list->length -= 1;
+}
+
+// varargs is a hack to accept any type as input
+void push_(RC *l, ...) {
+    List *list = (List *)l->reference;
 
     va_list ap;
     va_start(ap, l);
-    set_varargs_(l, list->length - 1, ap);
+    insert_varargs_(l, list->length, &ap);
     va_end(ap);
 
     // NOTE: do NOT destroy_noref here, since no reference is had
     // while a While loop is building a list
 }
 
-void set_(RC *l, int64_t idx, ...) {
+/*
+ * The list builtins, which unlike push_ might be given a temporary list
+ */
+
+void push(RC *l, ...) {
     List *list = (List *)l->reference;
-    assert(idx >= 0 && (size_t)idx < list->length);
+
+    va_list ap;
+    va_start(ap, l);
+    insert_varargs_(l, list->length, &ap);
+    va_end(ap);
+
+    destroy_noref(l);
+}
+
+/*
+ * The index can also be the length of the list, to insert at the end
+ */
+void insert(int64_t line, int64_t column, RC *l, int64_t idx, ...) {
+    List *list = (List *)l->reference;
+    int64_t normalized = normalize_index(idx, list->length);
+    if(normalized < 0 || (size_t)normalized > list->length)
+        index_out_of_range_(line, column, idx, list->length);
+    idx = normalized;
+
+    va_list ap;
+    va_start(ap, idx);
+    insert_varargs_(l, idx, &ap);
+    va_end(ap);
+
+    destroy_noref(l);
+}
+
+/*
+ * Removes the item at idx, an RC item is moved into dest without being destroyed
+ */
+ListItem remove_item(int64_t line, int64_t column, RC *l, int64_t idx, RC *dest) {
+    List *list = (List *)l->reference;
+    idx = check_index_(line, column, idx, list->length);
+
+    size_t size = item_size(list->item_type);
+    char *slot = (char *)list->items + idx * size;
+    ListItem item;
+    switch(list->item_type) {
+        case SW_INT: item.n = *(int64_t *)slot; break;
+        case SW_BOOL: item.b = *(bool *)slot; break;
+        case SW_UNIT: item.u = 0; break;
+        case SW_CHAR: item.c = *(int32_t *)slot; break;
+        case SW_FLOAT: item.f = *(double *)slot; break;
+        case SW_U8: item.n = *(uint8_t *)slot; break;
+        case SW_I32: item.n = *(int32_t *)slot; break;
+        case SW_U32: item.n = *(uint32_t *)slot; break;
+        case SW_U64: item.n = *(uint64_t *)slot; break;
+        case SW_STRING:
+        case SW_LIST:
+        case SW_MAP:
+        case SW_SET:
+        case SW_TUPLE:
+        case SW_RANGE:
+            // the list no longer holds a reference, so dest becomes a temporary
+            *dest = *(RC *)slot;
+            *dest->count -= 1;
+            item.rc = dest;
+            break;
+    }
+    remove_slot_(list, idx);
+
+    destroy_noref(l);
+    return item;
+}
+
+int64_t remove_int(int64_t line, int64_t column, RC *l, int64_t idx) {
+    return remove_item(line, column, l, idx, NULL).n;
+}
+
+bool remove_bool(int64_t line, int64_t column, RC *l, int64_t idx) {
+    return remove_item(line, column, l, idx, NULL).b;
+}
+
+bool remove_unit(int64_t line, int64_t column, RC *l, int64_t idx) {
+    return remove_item(line, column, l, idx, NULL).u;
+}
+
+int32_t remove_char(int64_t line, int64_t column, RC *l, int64_t idx) {
+    return remove_item(line, column, l, idx, NULL).c;
+}
+
+double remove_float(int64_t line, int64_t column, RC *l, int64_t idx) {
+    return remove_item(line, column, l, idx, NULL).f;
+}
+
+void remove_rc(RC *dest, int64_t line, int64_t column, RC *l, int64_t idx) {
+    remove_item(line, column, l, idx, dest);
+}
+
+int64_t pop_int(int64_t line, int64_t column, RC *l) {
+    check_nonempty_(line, column, l, "@pop");
+    return remove_int(line, column, l, -1);
+}
+
+bool pop_bool(int64_t line, int64_t column, RC *l) {
+    check_nonempty_(line, column, l, "@pop");
+    return remove_bool(line, column, l, -1);
+}
+
+bool pop_unit(int64_t line, int64_t column, RC *l) {
+    check_nonempty_(line, column, l, "@pop");
+    return remove_unit(line, column, l, -1);
+}
+
+int32_t pop_char(int64_t line, int64_t column, RC *l) {
+    check_nonempty_(line, column, l, "@pop");
+    return remove_char(line, column, l, -1);
+}
+
+double pop_float(int64_t line, int64_t column, RC *l) {
+    check_nonempty_(line, column, l, "@pop");
+    return remove_float(line, column, l, -1);
+}
+
+void pop_rc(RC *dest, int64_t line, int64_t column, RC *l) {
+    check_nonempty_(line, column, l, "@pop");
+    remove_rc(dest, line, column, l, -1);
+}
+
+void clear(RC *l) {
+    List *list = (List *)l->reference;
+    if(is_rc_item(list->item_type)) {
+        for(size_t i = 0; i < list->length; i++)
+            drop(((RC *)list->items) + i);
+    }
+    list->length = 0;
+    destroy_noref(l);
+}
+
+/*
+ * Appends the items of src to l, which might be the same list
+ */
+void extend(RC *l, RC *src) {
+    List *list = (List *)l->reference,
+         *src_list = (List *)src->reference;
+    size_t size = item_size(list->item_type),
+           length = src_list->length;
+
+    if(list->length + length > list->capacity && list->item_type != SW_UNIT) {
+        list->capacity = MAX(list->length + length, GROW_CAPACITY(list->capacity));
+        list->items = realloc(list->items, size * list->capacity);
+    }
+    // realloc might have moved src_list's items too, if it's the same list
+    memcpy((char *)list->items + list->length * size, src_list->items, length * size);
+    if(is_rc_item(list->item_type)) {
+        for(size_t i = 0; i < length; i++)
+            alloc(((RC *)list->items) + list->length + i);
+    }
+    list->length += length;
+
+    destroy_noref(src);
+    destroy_noref(l);
+}
+
+void set_(RC *l, int64_t line, int64_t column, int64_t idx, ...) {
+    List *list = (List *)l->reference;
+    idx = check_index_(line, column, idx, list->length);
 
     va_list ap;
     va_start(ap, idx);
@@ -151,18 +587,40 @@ void set_varargs_(RC *l, int64_t idx, va_list ap) {
             break;
         case SW_UNIT: // unit list just keeps track of the length
             break;
+        case SW_CHAR:
+            ((int32_t *)list->items)[idx] = va_arg(ap, int32_t);
+            break;
+        case SW_FLOAT:
+            ((double *)list->items)[idx] = va_arg(ap, double);
+            break;
+        case SW_U8:
+            ((uint8_t *)list->items)[idx] = va_arg(ap, int64_t);
+            break;
+        case SW_I32:
+            ((int32_t *)list->items)[idx] = va_arg(ap, int64_t);
+            break;
+        case SW_U32:
+            ((uint32_t *)list->items)[idx] = va_arg(ap, int64_t);
+            break;
+        case SW_U64:
+            ((uint64_t *)list->items)[idx] = va_arg(ap, int64_t);
+            break;
         case SW_STRING:
         case SW_LIST:
+        case SW_MAP:
+        case SW_SET:
+        case SW_TUPLE:
+        case SW_RANGE:
             drop(((RC *)list->items) + idx);
             ((RC *)list->items)[idx] = *alloc(va_arg(ap, RC *));
             break;
     }
 }
 
-RC *get_setter_(RC *l, int64_t idx) {
+RC *get_setter_(RC *l, int64_t line, int64_t column, int64_t idx) {
     List *list = (List *)l->reference;
-    assert(idx >= 0 && (size_t)idx < list->length);
-    assert(list->item_type == SW_LIST); 
+    idx = check_index_(line, column, idx, list->length);
+    assert(list->item_type == SW_LIST || list->item_type == SW_MAP);
     // theoretically that should be caught by the type checker,
     // but may as well throw it in
 
@@ -187,16 +645,26 @@ bool listeq(RC *l1, RC *l2) {
     }
 
     alloc(l1);
-    alloc(l2); // index_list will try to destroy them otherwise
+    alloc(l2); // index_list_ will try to destroy them otherwise
     bool equal = true;
     for(int64_t i = 0; i < (int64_t)list1->length; i++) {
-        ListItem item1 = index_list(l1, i),
-                 item2 = index_list(l2, i);
+        ListItem item1 = index_list_(l1, i),
+                 item2 = index_list_(l2, i);
         switch(list1->item_type) {
-            case SW_INT: equal &= item1.n == item2.n; break;
+            case SW_INT:
+            case SW_U8:
+            case SW_I32:
+            case SW_U32:
+            case SW_U64: equal &= item1.n == item2.n; break;
             case SW_BOOL: equal &= item1.b == item2.b; break;
+            case SW_CHAR: equal &= item1.c == item2.c; break;
+            case SW_FLOAT: equal &= compare_items(SW_FLOAT, &item1.f, &item2.f) == 0; break;
             case SW_STRING: equal &= streq(item1.rc, item2.rc); break;
             case SW_LIST: equal &= listeq(item1.rc, item2.rc); break;
+            case SW_TUPLE: equal &= tupleeq(item1.rc, item2.rc); break;
+            case SW_RANGE: equal &= rangeeq(item1.rc, item2.rc); break;
+            case SW_MAP:
+            case SW_SET: break; // the typechecker doesn't allow maps or sets to be compared
         }
 
         if(!equal) break;
@@ -207,3 +675,299 @@ bool listeq(RC *l1, RC *l2) {
 
     return equal;
 }
+
+/*
+ * Orders two items of the given type, which are stored the same way as in a list
+ * strings are ordered by code point, and lists and tuples lexicographically
+ * floats are totally ordered, with every NaN equal and after every other float
+ */
+int compare_items(ItemType item_type, void *a, void *b) {
+    switch(item_type) {
+        case SW_INT:
+            return (*(int64_t *)a > *(int64_t *)b) - (*(int64_t *)a < *(int64_t *)b);
+        case SW_BOOL:
+            return *(bool *)a - *(bool *)b;
+        case SW_UNIT:
+            return 0;
+        case SW_CHAR:
+            return (*(int32_t *)a > *(int32_t *)b) - (*(int32_t *)a < *(int32_t *)b);
+        case SW_FLOAT: {
+            // NaN sorts after every other float, and equals itself
+            double x = *(double *)a, y = *(double *)b;
+            if(isnan(x) || isnan(y))
+                return !!isnan(x) - !!isnan(y);
+            return (x > y) - (x < y);
+        }
+        case SW_U8:
+            return (*(uint8_t *)a > *(uint8_t *)b) - (*(uint8_t *)a < *(uint8_t *)b);
+        case SW_I32:
+            return (*(int32_t *)a > *(int32_t *)b) - (*(int32_t *)a < *(int32_t *)b);
+        case SW_U32:
+            return (*(uint32_t *)a > *(uint32_t *)b) - (*(uint32_t *)a < *(uint32_t *)b);
+        case SW_U64:
+            return (*(uint64_t *)a > *(uint64_t *)b) - (*(uint64_t *)a < *(uint64_t *)b);
+        case SW_STRING:
+            return strcmp(((String *)((RC *)a)->reference)->string,
+                          ((String *)((RC *)b)->reference)->string);
+        case SW_LIST:
+            return compare_lists((List *)((RC *)a)->reference, (List *)((RC *)b)->reference);
+        case SW_TUPLE:
+            return compare_tuples((Tuple *)((RC *)a)->reference, (Tuple *)((RC *)b)->reference);
+        case SW_RANGE:
+            return compare_ranges((Range *)((RC *)a)->reference, (Range *)((RC *)b)->reference);
+        case SW_MAP:
+        case SW_SET:
+            break; // the typechecker doesn't allow maps or sets to be compared
+    }
+    return 0;
+}
+
+int compare_lists(List *a, List *b) {
+    size_t size = item_size(a->item_type);
+    for(size_t i = 0; i < a->length && i < b->length; i++) {
+        int cmp = compare_items(a->item_type, (char *)a->items + i * size, (char *)b->items + i * size);
+        if(cmp != 0)
+            return cmp;
+    }
+    return (a->length > b->length) - (a->length < b->length);
+}
+
+// qsort doesn't pass any context to the comparison
+static ItemType sort_item_type;
+
+int compare_sort_items(const void *a, const void *b) {
+    return compare_items(sort_item_type, (void *)a, (void *)b);
+}
+
+void sort(RC *l) {
+    List *list = (List *)l->reference;
+    if(list->item_type != SW_UNIT) {
+        sort_item_type = list->item_type;
+        qsort(list->items, list->length, item_size(list->item_type), compare_sort_items);
+    }
+    destroy_noref(l);
+}
+
+void reverse(RC *l) {
+    List *list = (List *)l->reference;
+    size_t size = item_size(list->item_type);
+    RC temp; // big enough for any type of item
+    for(size_t i = 0; i < list->length / 2; i++) {
+        char *front = (char *)list->items + i * size,
+             *back = (char *)list->items + (list->length - i - 1) * size;
+        memcpy(&temp, front, size);
+        memcpy(front, back, size);
+        memcpy(back, &temp, size);
+    }
+    destroy_noref(l);
+}
+
+/*
+ * The index of the first item equal to the item in ap, or -1
+ */
+int64_t index_of_varargs_(RC *l, va_list *ap) {
+    List *list = (List *)l->reference;
+    size_t size = item_size(list->item_type);
+    RC item;
+    read_item_(list->item_type, ap, &item);
+
+    int64_t idx = -1;
+    for(size_t i = 0; i < list->length; i++) {
+        if(compare_items(list->item_type, (char *)list->items + i * size, &item) == 0) {
+            idx = i;
+            break;
+        }
+    }
+
+    if(is_rc_item(list->item_type))
+        destroy_noref(&item);
+    destroy_noref(l);
+    return idx;
+}
+
+int64_t index_of(RC *l, ...) {
+    va_list ap;
+    va_start(ap, l);
+    int64_t idx = index_of_varargs_(l, &ap);
+    va_end(ap);
+    return idx;
+}
+
+bool contains_list(RC *l, ...) {
+    va_list ap;
+    va_start(ap, l);
+    int64_t idx = index_of_varargs_(l, &ap);
+    va_end(ap);
+    return idx >= 0;
+}
+
+/*
+ * The smallest item of a list, or the largest if sign is -1, panicking if the list is empty
+ * an RC item is copied into dest, and is a temporary unless the list is still around
+ */
+ListItem extreme_item(RC *dest, int64_t line, int64_t column, RC *l, int sign) {
+    check_nonempty_(line, column, l, sign > 0 ? "@min" : "@max");
+    List *list = (List *)l->reference;
+
+    size_t size = item_size(list->item_type);
+    char *extreme = list->items;
+    for(size_t i = 1; i < list->length; i++) {
+        char *slot = (char *)list->items + i * size;
+        if(sign * compare_items(list->item_type, slot, extreme) < 0)
+            extreme = slot;
+    }
+
+    ListItem item;
+    switch(list->item_type) {
+        case SW_INT: item.n = *(int64_t *)extreme; break;
+        case SW_BOOL: item.b = *(bool *)extreme; break;
+        case SW_UNIT: item.u = 0; break;
+        case SW_CHAR: item.c = *(int32_t *)extreme; break;
+        case SW_FLOAT: item.f = *(double *)extreme; break;
+        case SW_U8: item.n = *(uint8_t *)extreme; break;
+        case SW_I32: item.n = *(int32_t *)extreme; break;
+        case SW_U32: item.n = *(uint32_t *)extreme; break;
+        case SW_U64: item.n = *(uint64_t *)extreme; break;
+        case SW_STRING:
+        case SW_LIST:
+        case SW_MAP:
+        case SW_SET:
+        case SW_TUPLE:
+        case SW_RANGE:
+            *dest = *alloc((RC *)extreme);
+            item.rc = dest;
+            break;
+    }
+
+    bool is_rc = is_rc_item(list->item_type);
+    destroy_noref(l);
+    if(is_rc)
+        *dest->count -= 1;
+    return item;
+}
+
+int64_t min_int(int64_t line, int64_t column, RC *l) {
+    return extreme_item(NULL, line, column, l, 1).n;
+}
+
+bool min_bool(int64_t line, int64_t column, RC *l) {
+    return extreme_item(NULL, line, column, l, 1).b;
+}
+
+bool min_unit(int64_t line, int64_t column, RC *l) {
+    return extreme_item(NULL, line, column, l, 1).u;
+}
+
+int32_t min_char(int64_t line, int64_t column, RC *l) {
+    return extreme_item(NULL, line, column, l, 1).c;
+}
+
+double min_float(int64_t line, int64_t column, RC *l) {
+    return extreme_item(NULL, line, column, l, 1).f;
+}
+
+void min_rc(RC *dest, int64_t line, int64_t column, RC *l) {
+    extreme_item(dest, line, column, l, 1);
+}
+
+int64_t max_int(int64_t line, int64_t column, RC *l) {
+    return extreme_item(NULL, line, column, l, -1).n;
+}
+
+bool max_bool(int64_t line, int64_t column, RC *l) {
+    return extreme_item(NULL, line, column, l, -1).b;
+}
+
+bool max_unit(int64_t line, int64_t column, RC *l) {
+    return extreme_item(NULL, line, column, l, -1).u;
+}
+
+int32_t max_char(int64_t line, int64_t column, RC *l) {
+    return extreme_item(NULL, line, column, l, -1).c;
+}
+
+double max_float(int64_t line, int64_t column, RC *l) {
+    return extreme_item(NULL, line, column, l, -1).f;
+}
+
+void max_rc(RC *dest, int64_t line, int64_t column, RC *l) {
+    extreme_item(dest, line, column, l, -1);
+}
+
+/*
+ * A list of (index, item) tuples
+ */
+void enumerate(RC *dest, RC *l) {
+    List *src = (List *)l->reference,
+         *list = empty_list(SW_TUPLE, src->length);
+    size_t size = item_size(src->item_type);
+    for(size_t i = 0; i < src->length; i++) {
+        Tuple *tuple = new_tuple_(2);
+        tuple->item_types[0] = SW_INT;
+        *(int64_t *)tuple->items = (int64_t)i;
+        tuple->item_types[1] = src->item_type;
+        memcpy(tuple->items + 1, (char *)src->items + i * size, size);
+        if(is_rc_item(src->item_type))
+            alloc(tuple->items + 1);
+
+        RC *slot = (RC *)list->items + i;
+        new(slot, tuple, (Destructor) destroy_tuple);
+        alloc(slot);
+        list->length++;
+    }
+
+    destroy_noref(l);
+    new(dest, list, (Destructor) destroy_list);
+}
+
+/*
+ * A list of (item1, item2) tuples, as long as the shorter list
+ */
+void zip(RC *dest, RC *l1, RC *l2) {
+    List *src1 = (List *)l1->reference,
+         *src2 = (List *)l2->reference;
+    size_t length = src1->length < src2->length ? src1->length : src2->length;
+    List *list = empty_list(SW_TUPLE, length);
+    size_t size1 = item_size(src1->item_type),
+           size2 = item_size(src2->item_type);
+    for(size_t i = 0; i < length; i++) {
+        Tuple *tuple = new_tuple_(2);
+        tuple->item_types[0] = src1->item_type;
+        memcpy(tuple->items, (char *)src1->items + i * size1, size1);
+        if(is_rc_item(src1->item_type))
+            alloc(tuple->items);
+        tuple->item_types[1] = src2->item_type;
+        memcpy(tuple->items + 1, (char *)src2->items + i * size2, size2);
+        if(is_rc_item(src2->item_type))
+            alloc(tuple->items + 1);
+
+        RC *slot = (RC *)list->items + i;
+        new(slot, tuple, (Destructor) destroy_tuple);
+        alloc(slot);
+        list->length++;
+    }
+
+    destroy_noref(l1);
+    destroy_noref(l2);
+    new(dest, list, (Destructor) destroy_list);
+}
+
+int listcmp(RC *l1, RC *l2) {
+    List *list1 = (List *)l1->reference,
+         *list2 = (List *)l2->reference;
+    assert(list1->item_type == list2->item_type);
+    // should be caught by type checker
+
+    int cmp = compare_lists(list1, list2);
+    destroy_noref(l1);
+    destroy_noref(l2);
+    return cmp;
+}
+
+bool listlt(RC *l1, RC *l2) {
+    return listcmp(l1, l2) < 0;
+}
+
+bool listleq(RC *l1, RC *l2) {
+    return listcmp(l1, l2) <= 0;
+}
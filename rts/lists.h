@@ -4,12 +4,28 @@ typedef enum ItemType {
     SW_UNIT,
     SW_STRING,
     SW_LIST,
+    SW_CHAR,
+    SW_MAP,
+    SW_SET,
+    SW_TUPLE,
+    SW_RANGE,
+    SW_FLOAT,
+    SW_U8,
+    SW_I32,
+    SW_U32,
+    SW_U64,
 } ItemType;
 
+/*
+ * Lists pack u8s, i32s and u32s into their own size,
+ * but they come and go from the RTS widened to 64 bits, the same as u64s, in n
+ */
 typedef union ListItem {
     int64_t n;
     bool b;
     bool u;
+    int32_t c;
+    double f;
     RC *rc;
 } ListItem;
 
@@ -20,16 +36,82 @@ typedef struct List {
     size_t capacity;
 } List;
 
+int64_t normalize_index(int64_t idx, size_t length);
+void index_out_of_range_(int64_t line, int64_t column, int64_t idx, size_t length);
+int64_t check_index_(int64_t line, int64_t column, int64_t idx, size_t length);
+void check_nonempty_(int64_t line, int64_t column, RC *l, char *builtin);
+void clamp_slice(int64_t *low, int64_t *high, size_t length);
+
+bool is_rc_item(ItemType item_type);
+size_t item_size(ItemType item_type);
 void destroy_list(List *list);
 void rc_list(RC *rc, ItemType item_type, size_t count, ...);
-ListItem index_list(RC *rc, int64_t idx);
+ListItem get_item(List *list, size_t idx);
+ListItem index_list(RC *l, int64_t line, int64_t column, int64_t idx);
+ListItem index_list_(RC *l, int64_t idx);
 int64_t length_list(RC *l);
+void slice_list(RC *dest, RC *src, int64_t low, int64_t high);
+void slice_list_range(RC *dest, RC *src, RC *r);
+List *empty_list(ItemType item_type, size_t length);
+void copy_items(List *list, List *src);
+void concat_list(RC *dest, RC *l1, RC *l2);
+void repeat_list(RC *dest, RC *src, int64_t n);
 
 int64_t as_int(ListItem item);
 bool as_bool(ListItem item);
 bool as_unit(ListItem item);
+int32_t as_char(ListItem item);
+double as_float(ListItem item);
 RC *as_rc(ListItem item);
 
+void read_item_(ItemType item_type, va_list *ap, void *slot);
+void insert_varargs_(RC *l, int64_t idx, va_list *ap);
+void insert_slot_(List *list, size_t idx, void *item);
+void remove_slot_(List *list, size_t idx);
 void push_(RC *l, ...);
-void set_(RC *l, int64_t idx, ...);
+void push(RC *l, ...);
+void insert(int64_t line, int64_t column, RC *l, int64_t idx, ...);
+ListItem remove_item(int64_t line, int64_t column, RC *l, int64_t idx, RC *dest);
+int64_t remove_int(int64_t line, int64_t column, RC *l, int64_t idx);
+bool remove_bool(int64_t line, int64_t column, RC *l, int64_t idx);
+bool remove_unit(int64_t line, int64_t column, RC *l, int64_t idx);
+int32_t remove_char(int64_t line, int64_t column, RC *l, int64_t idx);
+double remove_float(int64_t line, int64_t column, RC *l, int64_t idx);
+void remove_rc(RC *dest, int64_t line, int64_t column, RC *l, int64_t idx);
+int64_t pop_int(int64_t line, int64_t column, RC *l);
+bool pop_bool(int64_t line, int64_t column, RC *l);
+bool pop_unit(int64_t line, int64_t column, RC *l);
+int32_t pop_char(int64_t line, int64_t column, RC *l);
+double pop_float(int64_t line, int64_t column, RC *l);
+void pop_rc(RC *dest, int64_t line, int64_t column, RC *l);
+void clear(RC *l);
+void extend(RC *l, RC *src);
+void set_(RC *l, int64_t line, int64_t column, int64_t idx, ...);
 void set_varargs_(RC *l, int64_t idx, va_list ap);
+
+int compare_items(ItemType item_type, void *a, void *b);
+int compare_lists(List *a, List *b);
+void sort(RC *l);
+void reverse(RC *l);
+int64_t index_of_varargs_(RC *l, va_list *ap);
+int64_t index_of(RC *l, ...);
+bool contains_list(RC *l, ...);
+ListItem extreme_item(RC *dest, int64_t line, int64_t column, RC *l, int sign);
+int64_t min_int(int64_t line, int64_t column, RC *l);
+bool min_bool(int64_t line, int64_t column, RC *l);
+bool min_unit(int64_t line, int64_t column, RC *l);
+int32_t min_char(int64_t line, int64_t column, RC *l);
+double min_float(int64_t line, int64_t column, RC *l);
+void min_rc(RC *dest, int64_t line, int64_t column, RC *l);
+int64_t max_int(int64_t line, int64_t column, RC *l);
+bool max_bool(int64_t line, int64_t column, RC *l);
+bool max_unit(int64_t line, int64_t column, RC *l);
+int32_t max_char(int64_t line, int64_t column, RC *l);
+double max_float(int64_t line, int64_t column, RC *l);
+void max_rc(RC *dest, int64_t line, int64_t column, RC *l);
+void enumerate(RC *dest, RC *l);
+void zip(RC *dest, RC *l1, RC *l2);
+
+int listcmp(RC *l1, RC *l2);
+bool listlt(RC *l1, RC *l2);
+bool listleq(RC *l1, RC *l2);
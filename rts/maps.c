@@ -0,0 +1,549 @@
+#include <stdlib.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdarg.h>
+#include <assert.h>
+#include <string.h>
+#include <math.h>
+
+#include "rc.h"
+#include "lists.h"
+#include "strings.h"
+#include "maps.h"
+#include "tuples.h"
+#include "ranges.h"
+#include "panic.h"
+
+#define MIN_SLOTS 16
+
+/*
+ * Scrambles the bits of h, so that nearby ints don't end up in nearby slots
+ */
+uint64_t mix(uint64_t h) {
+    h ^= h >> 33;
+    h *= 0xff51afd7ed558ccdULL;
+    h ^= h >> 33;
+    h *= 0xc4ceb9fe1a85ec53ULL;
+    h ^= h >> 33;
+    return h;
+}
+
+/*
+ * Hashes an item which is stored the same way as in a list
+ * items which compare_items says are equal have the same hash
+ */
+uint64_t hash_item(ItemType item_type, void *item) {
+    switch(item_type) {
+        case SW_INT: return mix(*(int64_t *)item);
+        case SW_BOOL: return mix(*(bool *)item);
+        case SW_UNIT: return 0;
+        case SW_CHAR: return mix(*(int32_t *)item);
+        case SW_U8: return mix(*(uint8_t *)item);
+        case SW_I32: return mix(*(int32_t *)item);
+        case SW_U32: return mix(*(uint32_t *)item);
+        case SW_U64: return mix(*(uint64_t *)item);
+        case SW_FLOAT: {
+            // 0.0 and -0.0 are equal, as are all NaNs, but they have different bits
+            double f = *(double *)item;
+            if(f == 0) f = 0;
+            else if(isnan(f)) f = NAN;
+            uint64_t bits;
+            memcpy(&bits, &f, sizeof(bits));
+            return mix(bits);
+        }
+        case SW_STRING: {
+            // FNV-1a
+            String *string = (String *)((RC *)item)->reference;
+            uint64_t h = 0xcbf29ce484222325ULL;
+            for(size_t i = 0; i < string->length; i++) {
+                h ^= (unsigned char)string->string[i];
+                h *= 0x100000001b3ULL;
+            }
+            return h;
+        }
+        case SW_LIST: {
+            List *list = (List *)((RC *)item)->reference;
+            size_t size = item_size(list->item_type);
+            uint64_t h = mix(list->length);
+            for(size_t i = 0; i < list->length; i++)
+                h = 31 * h + hash_item(list->item_type, (char *)list->items + i * size);
+            return h;
+        }
+        case SW_TUPLE: {
+            Tuple *tuple = (Tuple *)((RC *)item)->reference;
+            uint64_t h = 0;
+            for(size_t i = 0; i < tuple->length; i++)
+                h = 31 * h + hash_item(tuple->item_types[i], tuple->items + i);
+            return h;
+        }
+        case SW_RANGE: {
+            // hashed like it's compared, so 0..=4 and 0..5 hash the same
+            Range range = exclusive_range_((Range *)((RC *)item)->reference);
+            uint64_t h = 31 * (31 * mix(range.start) + mix(range.stop)) + mix(range.step);
+            return mix(31 * h + range.inclusive);
+        }
+        case SW_MAP:
+        case SW_SET:
+            break; // the typechecker doesn't allow maps or sets in keys
+    }
+    return 0;
+}
+
+void destroy_map(Map *map) {
+    destroy_list(map->keys);
+    destroy_list(map->values);
+    free(map->slots);
+    free(map);
+}
+
+/*
+ * The slot which holds key, or the empty slot where it would go
+ */
+size_t find_slot_(Map *map, void *key) {
+    ItemType key_type = map->keys->item_type;
+    size_t size = item_size(key_type),
+           mask = map->capacity - 1,
+           slot = hash_item(key_type, key) & mask;
+    while(map->slots[slot] >= 0) {
+        void *other = (char *)map->keys->items + map->slots[slot] * size;
+        if(compare_items(key_type, other, key) == 0)
+            break;
+        slot = (slot + 1) & mask;
+    }
+    return slot;
+}
+
+/*
+ * Rebuilds the slots from the keys
+ */
+void rehash_(Map *map, size_t capacity) {
+    free(map->slots);
+    map->capacity = capacity;
+    map->slots = malloc(capacity * sizeof(int64_t));
+    for(size_t i = 0; i < capacity; i++)
+        map->slots[i] = -1;
+
+    size_t size = item_size(map->keys->item_type);
+    for(size_t i = 0; i < map->keys->length; i++)
+        map->slots[find_slot_(map, (char *)map->keys->items + i * size)] = i;
+}
+
+/*
+ * Removed entries stay in keys and values until the map is compacted,
+ * so an entry is only live if the slot for its key points back to it
+ */
+bool is_live_(Map *map, size_t idx) {
+    size_t size = item_size(map->keys->item_type);
+    return map->slots[find_slot_(map, (char *)map->keys->items + idx * size)] == (int64_t)idx;
+}
+
+/*
+ * Drops the removed entries, keeping the rest in the order they were added
+ */
+void compact_(Map *map) {
+    if(map->removed == 0)
+        return;
+
+    // the slots have to be read before any entry moves
+    size_t length = map->keys->length;
+    bool *live = malloc(length * sizeof(bool));
+    for(size_t i = 0; i < length; i++)
+        live[i] = is_live_(map, i);
+
+    List *lists[] = { map->keys, map->values };
+    for(size_t l = 0; l < 2; l++) {
+        List *list = lists[l];
+        size_t size = item_size(list->item_type),
+               kept = 0;
+        for(size_t i = 0; i < length; i++) {
+            void *item = (char *)list->items + i * size;
+            if(!live[i]) {
+                if(is_rc_item(list->item_type))
+                    drop(item);
+                continue;
+            }
+            memmove((char *)list->items + kept * size, item, size);
+            kept++;
+        }
+        list->length = kept;
+    }
+
+    free(live);
+    map->removed = 0;
+    rehash_(map, map->capacity);
+}
+
+/*
+ * Sets the value of key, taking a reference to whichever of them the map keeps
+ */
+void put_(Map *map, void *key, void *value) {
+    ItemType key_type = map->keys->item_type,
+             value_type = map->values->item_type;
+    if(is_rc_item(value_type))
+        alloc(value);
+
+    size_t slot = find_slot_(map, key);
+    if(map->slots[slot] >= 0) {
+        size_t size = item_size(value_type);
+        void *old = (char *)map->values->items + map->slots[slot] * size;
+        if(is_rc_item(value_type))
+            drop(old);
+        memcpy(old, value, size);
+        return;
+    }
+
+    if(is_rc_item(key_type))
+        alloc(key);
+    map->slots[slot] = map->keys->length;
+    insert_slot_(map->keys, map->keys->length, key);
+    insert_slot_(map->values, map->values->length, value);
+    // keep at least half of the slots empty, so that probing stays short
+    if(2 * map->keys->length > map->capacity) {
+        compact_(map);
+        if(2 * map->keys->length > map->capacity)
+            rehash_(map, 2 * map->capacity);
+    }
+}
+
+/*
+ * Reads a key and then a value from ap, and sets the value of that key
+ */
+void put_varargs_(Map *map, va_list *ap) {
+    ItemType key_type = map->keys->item_type;
+    RC key, value; // big enough for any type of item
+    read_item_(key_type, ap, &key);
+    read_item_(map->values->item_type, ap, &value);
+    put_(map, &key, &value);
+    // the key is still a temporary if it was already in the map
+    if(is_rc_item(key_type))
+        destroy_noref(&key);
+}
+
+Map *empty_map(ItemType key_type, ItemType value_type, size_t count) {
+    Map *map = malloc(sizeof(Map));
+    map->keys = empty_list(key_type, count);
+    map->values = empty_list(value_type, count);
+    map->slots = NULL;
+    map->removed = 0;
+    rehash_(map, MIN_SLOTS);
+    return map;
+}
+
+/*
+ * The varargs are count keys and values, alternating
+ * later entries replace earlier ones with the same key
+ */
+void rc_map(RC *rc, ItemType key_type, ItemType value_type, size_t count, ...) {
+    Map *map = empty_map(key_type, value_type, count);
+
+    va_list ap;
+    va_start(ap, count);
+    for(size_t i = 0; i < count; i++)
+        put_varargs_(map, &ap);
+    va_end(ap);
+
+    new(rc, map, (Destructor) destroy_map);
+}
+
+int64_t length_map(RC *m) {
+    Map *map = (Map *)m->reference;
+    int64_t length = (int64_t)(map->keys->length - map->removed);
+    destroy_noref(m);
+    return length;
+}
+
+/*
+ * The index of the key in ap, or -1 if it isn't in the map
+ */
+int64_t find_key_(Map *map, va_list *ap) {
+    ItemType key_type = map->keys->item_type;
+    RC key;
+    read_item_(key_type, ap, &key);
+    int64_t idx = map->slots[find_slot_(map, &key)];
+    if(is_rc_item(key_type))
+        destroy_noref(&key);
+    return idx;
+}
+
+/*
+ * Panics the same way as @panic, for a key which isn't in the map
+ */
+void missing_key_(int64_t line, int64_t column) {
+    panic_message_(line, column, "key not in map");
+}
+
+ListItem index_map(RC *m, int64_t line, int64_t column, ...) {
+    Map *map = (Map *)m->reference;
+
+    va_list ap;
+    va_start(ap, column);
+    int64_t idx = find_key_(map, &ap);
+    va_end(ap);
+    if(idx < 0)
+        missing_key_(line, column);
+
+    ListItem item = get_item(map->values, idx);
+    destroy_noref(m);
+    return item;
+}
+
+void set_map_(RC *m, ...) {
+    va_list ap;
+    va_start(ap, m);
+    put_varargs_((Map *)m->reference, &ap);
+    va_end(ap);
+}
+
+/*
+ * Like get_setter_, for assigning into a list or map which is a value of m
+ */
+RC *get_map_setter_(RC *m, ...) {
+    Map *map = (Map *)m->reference;
+    assert(map->values->item_type == SW_LIST || map->values->item_type == SW_MAP);
+
+    va_list ap;
+    va_start(ap, m);
+    int64_t idx = find_key_(map, &ap);
+    va_end(ap);
+    assert(idx >= 0);
+
+    return ((RC *)map->values->items) + idx;
+}
+
+bool has(RC *m, ...) {
+    va_list ap;
+    va_start(ap, m);
+    int64_t idx = find_key_((Map *)m->reference, &ap);
+    va_end(ap);
+
+    destroy_noref(m);
+    return idx >= 0;
+}
+
+/*
+ * Removes the entry at idx from the slots, with backward shift deletion
+ * the entry itself is dropped when the map is next compacted
+ */
+void remove_at_(Map *map, size_t idx) {
+    ItemType key_type = map->keys->item_type;
+    size_t size = item_size(key_type),
+           mask = map->capacity - 1,
+           hole = find_slot_(map, (char *)map->keys->items + idx * size);
+    // later slots in the same run move back into the hole, unless they're before their own hash
+    for(size_t slot = (hole + 1) & mask; map->slots[slot] >= 0; slot = (slot + 1) & mask) {
+        void *key = (char *)map->keys->items + map->slots[slot] * size;
+        size_t home = hash_item(key_type, key) & mask;
+        if(((slot - home) & mask) >= ((slot - hole) & mask)) {
+            map->slots[hole] = map->slots[slot];
+            hole = slot;
+        }
+    }
+    map->slots[hole] = -1;
+
+    // compacting once half the entries are removed keeps removal amortized O(1)
+    map->removed += 1;
+    if(2 * map->removed > map->keys->length)
+        compact_(map);
+}
+
+/*
+ * Removes the key in ap, and returns its value the same way as remove_item
+ */
+ListItem remove_entry(RC *dest, int64_t line, int64_t column, RC *m, va_list *ap) {
+    Map *map = (Map *)m->reference;
+    int64_t idx = find_key_(map, ap);
+    if(idx < 0)
+        missing_key_(line, column);
+
+    ListItem item = get_item(map->values, idx);
+    bool rc = is_rc_item(map->values->item_type);
+    if(rc) {
+        *dest = *item.rc;
+        item.rc = dest;
+        alloc(dest); // in case removing compacts the map, which drops its reference
+    }
+    remove_at_(map, idx);
+    // otherwise the map keeps its reference until it's compacted, and dest is a temporary
+    if(rc)
+        *dest->count -= 1;
+
+    destroy_noref(m);
+    return item;
+}
+
+int64_t remove_map_int(int64_t line, int64_t column, RC *m, ...) {
+    va_list ap;
+    va_start(ap, m);
+    int64_t n = remove_entry(NULL, line, column, m, &ap).n;
+    va_end(ap);
+    return n;
+}
+
+bool remove_map_bool(int64_t line, int64_t column, RC *m, ...) {
+    va_list ap;
+    va_start(ap, m);
+    bool b = remove_entry(NULL, line, column, m, &ap).b;
+    va_end(ap);
+    return b;
+}
+
+bool remove_map_unit(int64_t line, int64_t column, RC *m, ...) {
+    va_list ap;
+    va_start(ap, m);
+    bool u = remove_entry(NULL, line, column, m, &ap).u;
+    va_end(ap);
+    return u;
+}
+
+int32_t remove_map_char(int64_t line, int64_t column, RC *m, ...) {
+    va_list ap;
+    va_start(ap, m);
+    int32_t c = remove_entry(NULL, line, column, m, &ap).c;
+    va_end(ap);
+    return c;
+}
+
+double remove_map_float(int64_t line, int64_t column, RC *m, ...) {
+    va_list ap;
+    va_start(ap, m);
+    double f = remove_entry(NULL, line, column, m, &ap).f;
+    va_end(ap);
+    return f;
+}
+
+void remove_map_rc(RC *dest, int64_t line, int64_t column, RC *m, ...) {
+    va_list ap;
+    va_start(ap, m);
+    remove_entry(dest, line, column, m, &ap);
+    va_end(ap);
+}
+
+/*
+ * The keys or values of a map as a list, in the order they were added
+ */
+
+void keys(RC *dest, RC *m) {
+    Map *map = (Map *)m->reference;
+    compact_(map);
+    List *list = empty_list(map->keys->item_type, map->keys->length);
+    copy_items(list, map->keys);
+    destroy_noref(m);
+    new(dest, list, (Destructor) destroy_list);
+}
+
+void values(RC *dest, RC *m) {
+    Map *map = (Map *)m->reference;
+    compact_(map);
+    List *list = empty_list(map->values->item_type, map->values->length);
+    copy_items(list, map->values);
+    destroy_noref(m);
+    new(dest, list, (Destructor) destroy_list);
+}
+
+/*
+ * A set is a map whose values are all unit
+ */
+
+void add_varargs_(Map *set, va_list *ap) {
+    ItemType item_type = set->keys->item_type;
+    RC item;
+    bool u = 0;
+    read_item_(item_type, ap, &item);
+    put_(set, &item, &u);
+    if(is_rc_item(item_type))
+        destroy_noref(&item);
+}
+
+void rc_set(RC *rc, ItemType item_type, size_t count, ...) {
+    Map *set = empty_map(item_type, SW_UNIT, count);
+
+    va_list ap;
+    va_start(ap, count);
+    for(size_t i = 0; i < count; i++)
+        add_varargs_(set, &ap);
+    va_end(ap);
+
+    new(rc, set, (Destructor) destroy_map);
+}
+
+void add(RC *s, ...) {
+    va_list ap;
+    va_start(ap, s);
+    add_varargs_((Map *)s->reference, &ap);
+    va_end(ap);
+
+    destroy_noref(s);
+}
+
+/*
+ * Unlike removing from a map, the item doesn't have to be in the set
+ * returns whether it was
+ */
+bool remove_set(RC *s, ...) {
+    Map *set = (Map *)s->reference;
+
+    va_list ap;
+    va_start(ap, s);
+    int64_t idx = find_key_(set, &ap);
+    va_end(ap);
+
+    if(idx >= 0)
+        remove_at_(set, idx);
+    destroy_noref(s);
+    return idx >= 0;
+}
+
+bool contains_item_(Map *map, void *key) {
+    return map->slots[find_slot_(map, key)] >= 0;
+}
+
+/*
+ * The items of a which are or aren't in b, in the order they were added to a
+ */
+void filter_set_(RC *dest, RC *a, RC *b, bool keep) {
+    Map *set1 = (Map *)a->reference,
+        *set2 = (Map *)b->reference;
+    compact_(set1);
+    ItemType item_type = set1->keys->item_type;
+    size_t size = item_size(item_type);
+    Map *set = empty_map(item_type, SW_UNIT, 0);
+    bool u = 0;
+
+    for(size_t i = 0; i < set1->keys->length; i++) {
+        void *item = (char *)set1->keys->items + i * size;
+        if(contains_item_(set2, item) == keep)
+            put_(set, item, &u);
+    }
+
+    destroy_noref(a);
+    destroy_noref(b);
+    new(dest, set, (Destructor) destroy_map);
+}
+
+void set_union(RC *dest, RC *a, RC *b) {
+    Map *set1 = (Map *)a->reference,
+        *set2 = (Map *)b->reference;
+    compact_(set1);
+    compact_(set2);
+    ItemType item_type = set1->keys->item_type;
+    size_t size = item_size(item_type);
+    Map *set = empty_map(item_type, SW_UNIT, set1->keys->length + set2->keys->length);
+    bool u = 0;
+
+    for(size_t i = 0; i < set1->keys->length; i++)
+        put_(set, (char *)set1->keys->items + i * size, &u);
+    for(size_t i = 0; i < set2->keys->length; i++)
+        put_(set, (char *)set2->keys->items + i * size, &u);
+
+    destroy_noref(a);
+    destroy_noref(b);
+    new(dest, set, (Destructor) destroy_map);
+}
+
+void set_intersection(RC *dest, RC *a, RC *b) {
+    filter_set_(dest, a, b, true);
+}
+
+void set_difference(RC *dest, RC *a, RC *b) {
+    filter_set_(dest, a, b, false);
+}
@@ -0,0 +1,48 @@
+typedef struct Map {
+    List *keys;
+    List *values;
+    int64_t *slots; // indices into keys and values, or -1 for an empty slot
+    size_t capacity; // the number of slots, which is always a power of 2
+    size_t removed; // the number of entries in keys and values which have been removed
+} Map;
+
+uint64_t hash_item(ItemType item_type, void *item);
+void destroy_map(Map *map);
+size_t find_slot_(Map *map, void *key);
+void rehash_(Map *map, size_t capacity);
+bool is_live_(Map *map, size_t idx);
+void compact_(Map *map);
+void put_(Map *map, void *key, void *value);
+void put_varargs_(Map *map, va_list *ap);
+Map *empty_map(ItemType key_type, ItemType value_type, size_t count);
+void rc_map(RC *rc, ItemType key_type, ItemType value_type, size_t count, ...);
+int64_t length_map(RC *m);
+int64_t find_key_(Map *map, va_list *ap);
+
+void missing_key_(int64_t line, int64_t column);
+ListItem index_map(RC *m, int64_t line, int64_t column, ...);
+void set_map_(RC *m, ...);
+RC *get_map_setter_(RC *m, ...);
+bool has(RC *m, ...);
+
+void remove_at_(Map *map, size_t idx);
+ListItem remove_entry(RC *dest, int64_t line, int64_t column, RC *m, va_list *ap);
+int64_t remove_map_int(int64_t line, int64_t column, RC *m, ...);
+bool remove_map_bool(int64_t line, int64_t column, RC *m, ...);
+bool remove_map_unit(int64_t line, int64_t column, RC *m, ...);
+int32_t remove_map_char(int64_t line, int64_t column, RC *m, ...);
+double remove_map_float(int64_t line, int64_t column, RC *m, ...);
+void remove_map_rc(RC *dest, int64_t line, int64_t column, RC *m, ...);
+
+void keys(RC *dest, RC *m);
+void values(RC *dest, RC *m);
+
+void add_varargs_(Map *set, va_list *ap);
+void rc_set(RC *rc, ItemType item_type, size_t count, ...);
+void add(RC *s, ...);
+bool remove_set(RC *s, ...);
+bool contains_item_(Map *map, void *key);
+void filter_set_(RC *dest, RC *a, RC *b, bool keep);
+void set_union(RC *dest, RC *a, RC *b);
+void set_intersection(RC *dest, RC *a, RC *b);
+void set_difference(RC *dest, RC *a, RC *b);
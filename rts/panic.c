@@ -0,0 +1,46 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <stdint.h>
+#include <stdbool.h>
+
+#include "rc.h"
+#include "strings.h"
+#include "panic.h"
+
+/*
+ * message may be NULL, if the assertion didn't come with one
+ */
+void assert_(bool cond, int64_t line, int64_t column, RC *message) {
+    if(cond) {
+        if(message != NULL) destroy_noref(message);
+        return;
+    }
+
+    fflush(stdout);
+    fprintf(stderr, "assertion failed at line %ld, column %ld", line, column);
+    if(message != NULL)
+        fprintf(stderr, ": %s", ((String *)message->reference)->string);
+    fprintf(stderr, "\n");
+    exit(PANIC_STATUS);
+}
+
+void panic_(int64_t line, int64_t column, RC *message) {
+    fflush(stdout);
+    fprintf(stderr, "panicked at line %ld, column %ld: %s\n",
+            line, column, ((String *)message->reference)->string);
+    exit(PANIC_STATUS);
+}
+
+/*
+ * Panics with a message from the RTS itself, like an index which is out of range
+ */
+void panic_message_(int64_t line, int64_t column, char *message) {
+    RC rc;
+    rc_string(&rc, message);
+    panic_(line, column, &rc);
+}
+
+void exit_(int64_t status) {
+    fflush(stdout);
+    exit(status);
+}
@@ -0,0 +1,7 @@
+// the exit status of a failed assertion or a panic
+#define PANIC_STATUS 101
+
+void assert_(bool cond, int64_t line, int64_t column, RC *message);
+void panic_(int64_t line, int64_t column, RC *message);
+void panic_message_(int64_t line, int64_t column, char *message);
+void exit_(int64_t status);
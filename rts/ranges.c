@@ -0,0 +1,137 @@
+#include <stdlib.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <assert.h>
+
+#include "rc.h"
+#include "ranges.h"
+
+void destroy_range(Range *range) {
+    free(range);
+}
+
+/*
+ * Ranges are unboxed, so this is only used to put one in a list, map or tuple
+ */
+void rc_range(RC *rc, int64_t start, int64_t stop, int64_t step, bool inclusive) {
+    Range *range = malloc(sizeof(Range));
+    range->start = start;
+    range->stop = stop;
+    range->step = step;
+    range->inclusive = inclusive;
+    new(rc, range, (Destructor) destroy_range);
+}
+
+/*
+ * Reads the start, stop, step or inclusive flag of a boxed range
+ * NOTE: doesn't destroy_noref r, since unboxing reads all four
+ */
+int64_t index_range_(RC *r, int64_t idx) {
+    Range *range = (Range *)r->reference;
+    switch(idx) {
+        case 0: return range->start;
+        case 1: return range->stop;
+        case 2: return range->step;
+        default: return range->inclusive;
+    }
+}
+
+/*
+ * The same range with an exclusive stop, one past high in the direction of its step,
+ * unless that would overflow, in which case the stop stays included
+ */
+Range exclusive_range_(Range *range) {
+    Range exclusive = *range;
+    if(exclusive.inclusive) {
+        if(exclusive.step < 0 && exclusive.stop != INT64_MIN) {
+            exclusive.stop--;
+            exclusive.inclusive = false;
+        } else if(exclusive.step >= 0 && exclusive.stop != INT64_MAX) {
+            exclusive.stop++;
+            exclusive.inclusive = false;
+        }
+    }
+    return exclusive;
+}
+
+/*
+ * A step of 0 never gets anywhere, so it gives an empty range
+ */
+bool in_range_(Range *range, int64_t n) {
+    bool at_stop = range->inclusive && n == range->stop;
+    if(range->step > 0)
+        return range->start <= n && (n < range->stop || at_stop)
+            && (n - range->start) % range->step == 0;
+    else if(range->step < 0)
+        return (range->stop < n || at_stop) && n <= range->start
+            && (range->start - n) % range->step == 0;
+    else
+        return false;
+}
+
+/*
+ * Narrows range to the indices of something with the given length,
+ * returning how many there are
+ */
+size_t clamp_range_(Range *range, size_t length) {
+    int64_t len = (int64_t)length;
+    *range = exclusive_range_(range);
+    // a stop which is still included is the largest or smallest int,
+    // so it's past every index either way
+    if(range->inclusive) {
+        range->stop = range->step < 0 ? -1 : len;
+        range->inclusive = false;
+    }
+    if(range->step > 0) {
+        if(range->start < 0)
+            range->start += (-range->start + range->step - 1) / range->step * range->step;
+        if(range->stop > len)
+            range->stop = len;
+        if(range->start >= range->stop)
+            return 0;
+        return (range->stop - range->start + range->step - 1) / range->step;
+    } else if(range->step < 0) {
+        int64_t step = -range->step;
+        if(range->start >= len)
+            range->start -= (range->start - len + step) / step * step;
+        if(range->stop < -1)
+            range->stop = -1;
+        if(range->start <= range->stop)
+            return 0;
+        return (range->start - range->stop + step - 1) / step;
+    } else {
+        return 0;
+    }
+}
+
+bool contains_range(RC *r, int64_t n) {
+    bool contains = in_range_((Range *)r->reference, n);
+    destroy_noref(r);
+    return contains;
+}
+
+/*
+ * Ranges are ordered by their start, then exclusive stop, then whether it's still included,
+ * then step, so 0..=4 and 0..5 are equal
+ */
+int compare_ranges(Range *r1, Range *r2) {
+    Range a = exclusive_range_(r1), b = exclusive_range_(r2);
+    if(a.start != b.start)
+        return (a.start > b.start) - (a.start < b.start);
+    if(a.stop != b.stop)
+        return (a.stop > b.stop) - (a.stop < b.stop);
+    if(a.inclusive != b.inclusive)
+        return a.inclusive - b.inclusive;
+    return (a.step > b.step) - (a.step < b.step);
+}
+
+int rangecmp(RC *r1, RC *r2) {
+    int cmp = compare_ranges((Range *)r1->reference, (Range *)r2->reference);
+    destroy_noref(r1);
+    destroy_noref(r2);
+    return cmp;
+}
+
+bool rangeeq(RC *r1, RC *r2) {
+    return rangecmp(r1, r2) == 0;
+}
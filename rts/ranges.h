@@ -0,0 +1,18 @@
+typedef struct Range {
+    int64_t start;
+    int64_t stop; // high in both low..high and low..=high
+    int64_t step;
+    bool inclusive; // whether stop is included, as in low..=high
+} Range;
+
+void destroy_range(Range *range);
+void rc_range(RC *rc, int64_t start, int64_t stop, int64_t step, bool inclusive);
+int64_t index_range_(RC *r, int64_t idx);
+Range exclusive_range_(Range *range);
+bool in_range_(Range *range, int64_t n);
+size_t clamp_range_(Range *range, size_t length);
+bool contains_range(RC *r, int64_t n);
+
+int compare_ranges(Range *a, Range *b);
+int rangecmp(RC *r1, RC *r2);
+bool rangeeq(RC *r1, RC *r2);
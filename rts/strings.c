@@ -3,11 +3,104 @@
 #include <stdlib.h>
 #include <assert.h>
 #include <stdint.h>
+#include <stdarg.h>
+#include <errno.h>
+#include <ctype.h>
 
 #include "rc.h"
 #include "strings.h"
+#include "lists.h"
+#include "ranges.h"
 
-// TODO: properly handle UTF-8??
+/*
+ * Strings are UTF-8, and are indexed by code point
+ * any invalid bytes are treated as a code point on their own
+ */
+
+bool is_code_point_start(char c) {
+    return ((unsigned char)c & 0xC0) != 0x80;
+}
+
+/*
+ * The number of code points in the first `bytes` bytes of string
+ * this steps over the same code points as utf8_decode, so invalid bytes count on their own
+ */
+size_t utf8_length(char *string, size_t bytes) {
+    size_t length = 0, width;
+    for(size_t i = 0; i < bytes; i += width, length++)
+        utf8_decode(string + i, &width);
+    return length;
+}
+
+/*
+ * The byte offset of the idx'th code point, or the length of the string if there isn't one
+ */
+size_t utf8_offset(String *s, int64_t idx) {
+    size_t offset = 0, width;
+    for(; offset < s->length && idx > 0; offset += width, idx--)
+        utf8_decode(s->string + offset, &width);
+    return offset;
+}
+
+/*
+ * Decodes the code point at the start of string, storing how many bytes it takes in width
+ */
+int32_t utf8_decode(char *string, size_t *width) {
+    unsigned char *s = (unsigned char *)string;
+    if(s[0] < 0x80) {
+        *width = 1;
+        return s[0];
+    }
+
+    int32_t c;
+    if((s[0] & 0xE0) == 0xC0) {
+        *width = 2;
+        c = s[0] & 0x1F;
+    } else if((s[0] & 0xF0) == 0xE0) {
+        *width = 3;
+        c = s[0] & 0x0F;
+    } else if((s[0] & 0xF8) == 0xF0) {
+        *width = 4;
+        c = s[0] & 0x07;
+    } else {
+        *width = 1;
+        return 0xFFFD; // the replacement character
+    }
+
+    for(size_t i = 1; i < *width; i++) {
+        if(is_code_point_start(s[i])) { // also catches the NUL terminator
+            *width = 1;
+            return 0xFFFD;
+        }
+        c = (c << 6) | (s[i] & 0x3F);
+    }
+    return c;
+}
+
+/*
+ * Encodes c into buffer (which needs room for 4 bytes), returning the number of bytes used
+ */
+size_t utf8_encode(int32_t c, char *buffer) {
+    if(c < 0x80) {
+        buffer[0] = c;
+        return 1;
+    } else if(c < 0x800) {
+        buffer[0] = 0xC0 | (c >> 6);
+        buffer[1] = 0x80 | (c & 0x3F);
+        return 2;
+    } else if(c < 0x10000) {
+        buffer[0] = 0xE0 | (c >> 12);
+        buffer[1] = 0x80 | ((c >> 6) & 0x3F);
+        buffer[2] = 0x80 | (c & 0x3F);
+        return 3;
+    } else {
+        buffer[0] = 0xF0 | (c >> 18);
+        buffer[1] = 0x80 | ((c >> 12) & 0x3F);
+        buffer[2] = 0x80 | ((c >> 6) & 0x3F);
+        buffer[3] = 0x80 | (c & 0x3F);
+        return 4;
+    }
+}
 
 bool streq(RC *s1, RC *s2) {
     String *string1 = (String *)s1->reference,
@@ -18,6 +111,26 @@ bool streq(RC *s1, RC *s2) {
     return cmp;
 }
 
+/*
+ * Strings are ordered by their bytes, which is the same as ordering by code points for UTF-8
+ */
+int strcmp_rc(RC *s1, RC *s2) {
+    String *string1 = (String *)s1->reference,
+           *string2 = (String *)s2->reference;
+    int cmp = strcmp(string1->string, string2->string);
+    destroy_noref(s1);
+    destroy_noref(s2);
+    return cmp;
+}
+
+bool strlt(RC *s1, RC *s2) {
+    return strcmp_rc(s1, s2) < 0;
+}
+
+bool strleq(RC *s1, RC *s2) {
+    return strcmp_rc(s1, s2) <= 0;
+}
+
 void append(RC *rc, RC *s1, RC *s2) {
     String *string1 = (String *)s1->reference,
            *string2 = (String *)s2->reference,
@@ -48,15 +161,33 @@ void rc_string(RC *rc, char *string) {
     new(rc, s, (Destructor) destroy_string);
 }
 
-void index_string1(RC *dest, RC *src, int64_t idx) {
+/*
+ * Creates a RC for a string which has already been malloced
+ * the RC takes ownership of the string
+ */
+void rc_string_owned(RC *rc, char *string, size_t length) {
+    String *s = malloc(sizeof(String));
+    s->string = string;
+    s->length = length;
+    new(rc, s, (Destructor) destroy_string);
+}
+
+void index_string1(RC *dest, int64_t line, int64_t column, RC *src, int64_t idx) {
+    String *src_string = (String *)src->reference;
+    size_t length = utf8_length(src_string->string, src_string->length);
+    idx = check_index_(line, column, idx, length);
     index_string2(dest, src, idx, idx+1);
 }
 
-// TODO: fancy things ala python string indexing
+/*
+ * Slices the code points [low, high) ala python: negative bounds count from the end,
+ * and out of range bounds get clamped
+ */
 void index_string2(RC *dest, RC *src, int64_t low, int64_t high) {
     String *src_string = (String *)src->reference;
-    assert(low >= 0 && high >= 0);
-    assert(low <= high && (size_t)high <= src_string->length);
+    clamp_slice(&low, &high, utf8_length(src_string->string, src_string->length));
+    low = utf8_offset(src_string, low);
+    high = utf8_offset(src_string, high);
 
     String *str = malloc(sizeof(String));
     str->length = high - low;
@@ -69,9 +200,374 @@ void index_string2(RC *dest, RC *src, int64_t low, int64_t high) {
     new(dest, str, (Destructor) destroy_string);
 }
 
+/*
+ * The code points at each index of the range r which is in bounds, in the order r goes
+ */
+void index_string_range(RC *dest, RC *src, RC *r) {
+    String *src_string = (String *)src->reference;
+    size_t length = utf8_length(src_string->string, src_string->length);
+    Range range = *(Range *)r->reference;
+    size_t count = clamp_range_(&range, length);
+
+    // where each code point starts, and where the string ends
+    size_t *offsets = malloc((length + 1) * sizeof(size_t));
+    size_t n = 0, width;
+    for(size_t i = 0; i < src_string->length; i += width) {
+        offsets[n++] = i;
+        utf8_decode(src_string->string + i, &width);
+    }
+    offsets[length] = src_string->length;
+
+    char *string = malloc(src_string->length + 1);
+    size_t bytes = 0;
+    for(size_t i = 0; i < count; i++) {
+        int64_t idx = range.start + (int64_t)i * range.step;
+        size_t width = offsets[idx + 1] - offsets[idx];
+        memcpy(string + bytes, src_string->string + offsets[idx], width);
+        bytes += width;
+    }
+    string[bytes] = '\0';
+    free(offsets);
+
+    destroy_noref(src);
+    destroy_noref(r);
+    rc_string_owned(dest, string, bytes);
+}
+
+/*
+ * Repeats src n times, a negative n gives an empty string
+ */
+void repeat_string(RC *dest, RC *src, int64_t n) {
+    String *str = (String *)src->reference;
+    n = n < 0 ? 0 : n;
+    size_t length = str->length * n;
+    char *string = malloc(length + 1);
+    for(int64_t i = 0; i < n; i++)
+        memcpy(string + i * str->length, str->string, str->length);
+    string[length] = '\0';
+
+    destroy_noref(src);
+    rc_string_owned(dest, string, length);
+}
+
 int64_t length_string(RC *s) {
     String *str = (String *)s->reference;
-    int64_t length = (int64_t)str->length;
+    int64_t length = (int64_t)utf8_length(str->string, str->length);
     destroy_noref(s);
     return length;
 }
+
+/*
+ * Creates a list of the code points in src
+ */
+void chars_(RC *dest, RC *src) {
+    String *str = (String *)src->reference;
+    rc_list(dest, SW_CHAR, 0);
+    size_t width;
+    for(size_t i = 0; i < str->length; i += width)
+        push_(dest, utf8_decode(str->string + i, &width));
+    destroy_noref(src);
+}
+
+/*
+ * Creates a list of the bytes in src
+ */
+void bytes_(RC *dest, RC *src) {
+    String *str = (String *)src->reference;
+    rc_list(dest, SW_INT, 0);
+    for(size_t i = 0; i < str->length; i++)
+        push_(dest, (int64_t)(unsigned char)str->string[i]);
+    destroy_noref(src);
+}
+
+/*
+ * dest is an empty list if src isn't an integer, otherwise a list of just that integer
+ */
+void parse_int(RC *dest, RC *src) {
+    String *str = (String *)src->reference;
+    char *end;
+    errno = 0;
+    int64_t n = strtoll(str->string, &end, 10);
+    bool valid = str->length > 0 && !isspace(str->string[0])
+        && end == str->string + str->length && errno == 0;
+
+    if(valid)
+        rc_list(dest, SW_INT, 1, n);
+    else
+        rc_list(dest, SW_INT, 0);
+    destroy_noref(src);
+}
+
+/*
+ * dest is an empty list unless src is exactly "true" or "false"
+ */
+void parse_bool(RC *dest, RC *src) {
+    String *str = (String *)src->reference;
+    if(strcmp(str->string, "true") == 0)
+        rc_list(dest, SW_BOOL, 1, true);
+    else if(strcmp(str->string, "false") == 0)
+        rc_list(dest, SW_BOOL, 1, false);
+    else
+        rc_list(dest, SW_BOOL, 0);
+    destroy_noref(src);
+}
+
+/*
+ * Pushes a copy of the length bytes at start onto the list of strings dest
+ */
+void push_substring(RC *dest, char *start, size_t length) {
+    char *string = malloc(length + 1);
+    memcpy(string, start, length);
+    string[length] = '\0';
+
+    RC rc;
+    rc_string_owned(&rc, string, length);
+    push_(dest, &rc);
+}
+
+/*
+ * Splits src on every occurrence of sep
+ * an empty separator splits src into its code points
+ */
+void split(RC *dest, RC *src, RC *sep) {
+    String *src_str = (String *)src->reference,
+           *sep_str = (String *)sep->reference;
+    rc_list(dest, SW_STRING, 0);
+
+    if(sep_str->length == 0) {
+        size_t width;
+        for(size_t i = 0; i < src_str->length; i += width) {
+            utf8_decode(src_str->string + i, &width);
+            push_substring(dest, src_str->string + i, width);
+        }
+    } else {
+        char *start = src_str->string, *end;
+        while((end = strstr(start, sep_str->string)) != NULL) {
+            push_substring(dest, start, end - start);
+            start = end + sep_str->length;
+        }
+        push_substring(dest, start, src_str->string + src_str->length - start);
+    }
+
+    destroy_noref(src);
+    destroy_noref(sep);
+}
+
+void join(RC *dest, RC *strings, RC *sep) {
+    List *list = (List *)strings->reference;
+    String *sep_str = (String *)sep->reference;
+
+    size_t length = 0;
+    for(size_t i = 0; i < list->length; i++) {
+        String *str = (String *)((RC *)list->items)[i].reference;
+        length += str->length + (i > 0 ? sep_str->length : 0);
+    }
+
+    char *string = malloc(length + 1);
+    size_t offset = 0;
+    for(size_t i = 0; i < list->length; i++) {
+        String *str = (String *)((RC *)list->items)[i].reference;
+        if(i > 0) {
+            memcpy(string + offset, sep_str->string, sep_str->length);
+            offset += sep_str->length;
+        }
+        memcpy(string + offset, str->string, str->length);
+        offset += str->length;
+    }
+    string[length] = '\0';
+
+    destroy_noref(strings);
+    destroy_noref(sep);
+    rc_string_owned(dest, string, length);
+}
+
+/*
+ * Removes leading and trailing (ASCII) whitespace
+ */
+void trim(RC *dest, RC *src) {
+    String *str = (String *)src->reference;
+    size_t low = 0, high = str->length;
+    while(low < high && isspace((unsigned char)str->string[low]))
+        low++;
+    while(high > low && isspace((unsigned char)str->string[high - 1]))
+        high--;
+
+    char *string = malloc(high - low + 1);
+    memcpy(string, str->string + low, high - low);
+    string[high - low] = '\0';
+
+    destroy_noref(src);
+    rc_string_owned(dest, string, high - low);
+}
+
+/*
+ * The (code point) index of the first occurrence of needle in haystack, or -1
+ */
+int64_t find(RC *haystack, RC *needle) {
+    String *haystack_str = (String *)haystack->reference,
+           *needle_str = (String *)needle->reference;
+    char *found = strstr(haystack_str->string, needle_str->string);
+    int64_t idx = found == NULL
+        ? -1
+        : (int64_t)utf8_length(haystack_str->string, found - haystack_str->string);
+
+    destroy_noref(haystack);
+    destroy_noref(needle);
+    return idx;
+}
+
+/*
+ * Replaces every occurrence of from in src with to
+ * an empty from leaves src unchanged
+ */
+void replace(RC *dest, RC *src, RC *from, RC *to) {
+    String *src_str = (String *)src->reference,
+           *from_str = (String *)from->reference,
+           *to_str = (String *)to->reference;
+
+    size_t count = 0;
+    if(from_str->length > 0) {
+        char *start = src_str->string;
+        while((start = strstr(start, from_str->string)) != NULL) {
+            count++;
+            start += from_str->length;
+        }
+    }
+
+    size_t length = src_str->length + count * to_str->length - count * from_str->length;
+    char *string = malloc(length + 1);
+    char *start = src_str->string, *end;
+    size_t offset = 0;
+    for(size_t i = 0; i < count; i++) {
+        end = strstr(start, from_str->string);
+        memcpy(string + offset, start, end - start);
+        offset += end - start;
+        memcpy(string + offset, to_str->string, to_str->length);
+        offset += to_str->length;
+        start = end + from_str->length;
+    }
+    strcpy(string + offset, start);
+
+    destroy_noref(src);
+    destroy_noref(from);
+    destroy_noref(to);
+    rc_string_owned(dest, string, length);
+}
+
+bool starts_with(RC *src, RC *prefix) {
+    String *src_str = (String *)src->reference,
+           *prefix_str = (String *)prefix->reference;
+    bool result = prefix_str->length <= src_str->length
+        && memcmp(src_str->string, prefix_str->string, prefix_str->length) == 0;
+
+    destroy_noref(src);
+    destroy_noref(prefix);
+    return result;
+}
+
+bool ends_with(RC *src, RC *suffix) {
+    String *src_str = (String *)src->reference,
+           *suffix_str = (String *)suffix->reference;
+    bool result = suffix_str->length <= src_str->length
+        && memcmp(src_str->string + src_str->length - suffix_str->length,
+                  suffix_str->string, suffix_str->length) == 0;
+
+    destroy_noref(src);
+    destroy_noref(suffix);
+    return result;
+}
+
+bool contains_string(RC *haystack, RC *needle) {
+    String *haystack_str = (String *)haystack->reference,
+           *needle_str = (String *)needle->reference;
+    bool result = strstr(haystack_str->string, needle_str->string) != NULL;
+
+    destroy_noref(haystack);
+    destroy_noref(needle);
+    return result;
+}
+
+/*
+ * Maps every byte of src through f, so only ASCII letters get converted
+ */
+void map_bytes(RC *dest, RC *src, int (*f)(int)) {
+    String *str = (String *)src->reference;
+    char *string = malloc(str->length + 1);
+    for(size_t i = 0; i < str->length; i++)
+        string[i] = (unsigned char)str->string[i] < 0x80 ? f(str->string[i]) : str->string[i];
+    string[str->length] = '\0';
+
+    size_t length = str->length;
+    destroy_noref(src);
+    rc_string_owned(dest, string, length);
+}
+
+void to_upper(RC *dest, RC *src) {
+    map_bytes(dest, src, toupper);
+}
+
+void to_lower(RC *dest, RC *src) {
+    map_bytes(dest, src, tolower);
+}
+
+/*
+ * Concatenates count strings, which are passed as varargs
+ */
+void concat(RC *dest, int64_t count, ...) {
+    va_list ap;
+    va_start(ap, count);
+    RC **strings = malloc(count * sizeof(RC *));
+    size_t length = 0;
+    for(int64_t i = 0; i < count; i++) {
+        strings[i] = va_arg(ap, RC *);
+        length += ((String *)strings[i]->reference)->length;
+    }
+    va_end(ap);
+
+    char *string = malloc(length + 1);
+    size_t offset = 0;
+    for(int64_t i = 0; i < count; i++) {
+        String *str = (String *)strings[i]->reference;
+        memcpy(string + offset, str->string, str->length);
+        offset += str->length;
+        destroy_noref(strings[i]);
+    }
+    string[length] = '\0';
+    free(strings);
+    rc_string_owned(dest, string, length);
+}
+
+/*
+ * Pads src with fill until it is width code points long
+ * align is one of < ^ >, and zero pads with 0s after any sign
+ */
+void pad(RC *dest, RC *src, int32_t fill, int32_t align, int64_t width, bool zero) {
+    String *str = (String *)src->reference;
+    size_t length = utf8_length(str->string, str->length);
+    size_t padding = (size_t)width > length ? (size_t)width - length : 0;
+    if(zero) {
+        fill = '0';
+        align = '>';
+    }
+
+    size_t before = align == '>' ? padding : (align == '^' ? padding / 2 : 0),
+           after = padding - before;
+    char fill_bytes[4];
+    size_t fill_width = utf8_encode(fill, fill_bytes);
+
+    size_t bytes = str->length + padding * fill_width, offset = 0;
+    char *string = malloc(bytes + 1);
+    size_t sign = zero && str->string[0] == '-';
+    memcpy(string, str->string, sign);
+    offset += sign;
+    for(size_t i = 0; i < before; i++, offset += fill_width)
+        memcpy(string + offset, fill_bytes, fill_width);
+    memcpy(string + offset, str->string + sign, str->length - sign);
+    offset += str->length - sign;
+    for(size_t i = 0; i < after; i++, offset += fill_width)
+        memcpy(string + offset, fill_bytes, fill_width);
+    string[bytes] = '\0';
+
+    destroy_noref(src);
+    rc_string_owned(dest, string, bytes);
+}
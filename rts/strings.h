@@ -4,10 +4,39 @@ typedef struct String {
 } String;
 
 bool streq(RC *s1, RC *s2);
+int strcmp_rc(RC *s1, RC *s2);
+bool strlt(RC *s1, RC *s2);
+bool strleq(RC *s1, RC *s2);
 void append(RC *rc, RC *s1, RC *s2);
 void destroy_string(String *s);
 void rc_string(RC *rc, char *s);
+void rc_string_owned(RC *rc, char *string, size_t length);
 
-void index_string1(RC *dest, RC *src, int64_t idx);
+void index_string1(RC *dest, int64_t line, int64_t column, RC *src, int64_t idx);
 void index_string2(RC *dest, RC *src, int64_t low, int64_t high);
+void index_string_range(RC *dest, RC *src, RC *r);
+void repeat_string(RC *dest, RC *src, int64_t n);
 int64_t length_string(RC *s);
+
+size_t utf8_length(char *string, size_t bytes);
+size_t utf8_offset(String *s, int64_t idx);
+int32_t utf8_decode(char *string, size_t *width);
+size_t utf8_encode(int32_t c, char *buffer);
+void chars_(RC *dest, RC *src);
+void bytes_(RC *dest, RC *src);
+
+void parse_int(RC *dest, RC *src);
+void parse_bool(RC *dest, RC *src);
+
+void split(RC *dest, RC *src, RC *sep);
+void join(RC *dest, RC *strings, RC *sep);
+void trim(RC *dest, RC *src);
+int64_t find(RC *haystack, RC *needle);
+void replace(RC *dest, RC *src, RC *from, RC *to);
+bool starts_with(RC *src, RC *prefix);
+bool ends_with(RC *src, RC *suffix);
+bool contains_string(RC *haystack, RC *needle);
+void to_upper(RC *dest, RC *src);
+void to_lower(RC *dest, RC *src);
+void concat(RC *dest, int64_t count, ...);
+void pad(RC *dest, RC *src, int32_t fill, int32_t align, int64_t width, bool zero);
@@ -0,0 +1,57 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <stdbool.h>
+#include <unistd.h>
+#include <sys/wait.h>
+
+#include "tests.h"
+
+static int passed = 0;
+static int failed = 0;
+
+/*
+ * Runs a test in its own process, so that a failing test can't affect any of the others
+ * returns true in the child (which should then run the test), and false in the parent
+ */
+bool begin_test(char *name) {
+    fflush(stdout); // otherwise the child inherits (and prints) whatever is buffered
+
+    pid_t pid = fork();
+    if(pid < 0) {
+        perror("fork");
+        exit(EXIT_FAILURE);
+    } else if(pid == 0) {
+        // tests read from /dev/null, so they can't wait on (or steal) the harness's input
+        if(freopen("/dev/null", "r", stdin) == NULL) {
+            perror("/dev/null");
+            exit(EXIT_FAILURE);
+        }
+        return true;
+    }
+
+    int status;
+    waitpid(pid, &status, 0);
+    if(WIFEXITED(status) && WEXITSTATUS(status) == 0) {
+        passed += 1;
+        printf("test \"%s\" ... ok\n", name);
+    } else {
+        failed += 1;
+        printf("test \"%s\" ... FAILED\n", name);
+    }
+    return false;
+}
+
+/*
+ * Called by the child once the test body finishes without failing
+ */
+void end_test() {
+    exit(EXIT_SUCCESS);
+}
+
+/*
+ * returns the exit status of the harness
+ */
+int finish_tests() {
+    printf("\n%d passed; %d failed\n", passed, failed);
+    return failed == 0 ? EXIT_SUCCESS : EXIT_FAILURE;
+}
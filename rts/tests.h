@@ -0,0 +1,3 @@
+bool begin_test(char *name);
+void end_test();
+int finish_tests();
@@ -0,0 +1,116 @@
+#include <stdlib.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdarg.h>
+#include <assert.h>
+
+#include "rc.h"
+#include "lists.h"
+#include "tuples.h"
+
+void destroy_tuple(Tuple *tuple) {
+    for(size_t i = 0; i < tuple->length; i++) {
+        if(is_rc_item(tuple->item_types[i]))
+            drop(tuple->items + i);
+    }
+    free(tuple->item_types);
+    free(tuple->items);
+    free(tuple);
+}
+
+/*
+ * A tuple with room for count items, which the caller fills in
+ */
+Tuple *new_tuple_(size_t count) {
+    Tuple *tuple = malloc(sizeof(Tuple));
+    tuple->length = count;
+    tuple->item_types = malloc(count * sizeof(ItemType));
+    tuple->items = malloc(count * sizeof(RC));
+    return tuple;
+}
+
+/*
+ * The varargs are the ItemType and then the value of each of the count items
+ */
+void rc_tuple(RC *rc, size_t count, ...) {
+    Tuple *tuple = new_tuple_(count);
+
+    va_list ap;
+    va_start(ap, count);
+    for(size_t i = 0; i < count; i++) {
+        ItemType item_type = (ItemType)va_arg(ap, int);
+        tuple->item_types[i] = item_type;
+        read_item_(item_type, &ap, tuple->items + i);
+        if(is_rc_item(item_type))
+            alloc(tuple->items + i);
+    }
+    va_end(ap);
+
+    new(rc, tuple, (Destructor) destroy_tuple);
+}
+
+/*
+ * Like get_item, an RC item points into the tuple
+ */
+ListItem get_tuple_item(Tuple *tuple, size_t idx) {
+    ListItem item;
+    void *slot = tuple->items + idx;
+    switch(tuple->item_types[idx]) {
+        case SW_INT: item.n = *(int64_t *)slot; break;
+        case SW_BOOL: item.b = *(bool *)slot; break;
+        case SW_UNIT: item.u = 0; break;
+        case SW_CHAR: item.c = *(int32_t *)slot; break;
+        case SW_FLOAT: item.f = *(double *)slot; break;
+        case SW_U8: item.n = *(uint8_t *)slot; break;
+        case SW_I32: item.n = *(int32_t *)slot; break;
+        case SW_U32: item.n = *(uint32_t *)slot; break;
+        case SW_U64: item.n = *(uint64_t *)slot; break;
+        case SW_STRING:
+        case SW_LIST:
+        case SW_MAP:
+        case SW_SET:
+        case SW_TUPLE:
+        case SW_RANGE: item.rc = (RC *)slot; break;
+    }
+    return item;
+}
+
+/*
+ * NOTE: doesn't destroy_noref t, since destructuring reads every item of the same tuple
+ */
+ListItem index_tuple_(RC *t, int64_t idx) {
+    Tuple *tuple = (Tuple *)t->reference;
+    assert(idx >= 0 && (size_t)idx < tuple->length);
+    return get_tuple_item(tuple, idx);
+}
+
+/*
+ * Tuples of the same type are ordered lexicographically, like lists
+ */
+int compare_tuples(Tuple *a, Tuple *b) {
+    for(size_t i = 0; i < a->length; i++) {
+        int cmp = compare_items(a->item_types[i], a->items + i, b->items + i);
+        if(cmp != 0)
+            return cmp;
+    }
+    return 0;
+}
+
+int tuplecmp(RC *t1, RC *t2) {
+    int cmp = compare_tuples((Tuple *)t1->reference, (Tuple *)t2->reference);
+    destroy_noref(t1);
+    destroy_noref(t2);
+    return cmp;
+}
+
+bool tupleeq(RC *t1, RC *t2) {
+    return tuplecmp(t1, t2) == 0;
+}
+
+bool tuplelt(RC *t1, RC *t2) {
+    return tuplecmp(t1, t2) < 0;
+}
+
+bool tupleleq(RC *t1, RC *t2) {
+    return tuplecmp(t1, t2) <= 0;
+}
@@ -0,0 +1,17 @@
+typedef struct Tuple {
+    size_t length;
+    ItemType *item_types;
+    RC *items; // each item is stored at the start of its own slot, the same way as in a list
+} Tuple;
+
+void destroy_tuple(Tuple *tuple);
+Tuple *new_tuple_(size_t count);
+void rc_tuple(RC *rc, size_t count, ...);
+ListItem get_tuple_item(Tuple *tuple, size_t idx);
+ListItem index_tuple_(RC *t, int64_t idx);
+
+int compare_tuples(Tuple *a, Tuple *b);
+int tuplecmp(RC *t1, RC *t2);
+bool tupleeq(RC *t1, RC *t2);
+bool tuplelt(RC *t1, RC *t2);
+bool tupleleq(RC *t1, RC *t2);
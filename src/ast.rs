@@ -43,9 +43,13 @@ where
     T: Tag,
 {
     Declare(T::DeclareTag, T::VariableID, Box<Expression<T>>),
-    Break,
-    Continue,
+    // (int a, string b) = pair;
+    Destructure(Vec<(T::DeclareTag, T::VariableID)>, Box<Expression<T>>),
+    // break 'outer value; where both the label and the value are optional
+    Break(Option<String>, Option<(T::TypeTag, Box<Expression<T>>)>),
+    Continue(Option<String>),
     Expression(Box<Expression<T>>),
+    Test(String, Body<T>),
 }
 
 #[derive(Debug, Clone)]
@@ -54,10 +58,34 @@ pub enum Type {
     String,
     Bool,
     Unit,
+    Char,
     List(Box<Type>),
+    Map(Box<Type>, Box<Type>),
+    Set(Box<Type>),
+    Tuple(Vec<Type>),
+    Range,
+    Float,
+    U8,
+    I32,
+    U32,
+    U64,
     Fn(Box<Type>, Vec<Type>),
 }
 
+impl Type {
+    /*
+     * Maps, sets and functions can't be compared or hashed, so they can't be map keys or set items
+     */
+    pub fn is_hashable(&self) -> bool {
+        match self {
+            Type::List(typ) => typ.is_hashable(),
+            Type::Tuple(types) => types.iter().all(Type::is_hashable),
+            Type::Map(_, _) | Type::Set(_) | Type::Fn(_, _) => false,
+            _ => true,
+        }
+    }
+}
+
 // make this a tagged statement?
 #[derive(Debug, Clone)]
 pub struct Body<T>
@@ -67,6 +95,22 @@ where
     pub statements: Vec<TaggedStatement<T>>,
 }
 
+impl<T> Body<T>
+where
+    T: Tag,
+{
+    // a body which ends in a break or continue never produces a value
+    pub fn ends_in_jump(&self) -> bool {
+        matches!(
+            self.statements.last(),
+            Some(TaggedStatement {
+                statement: Statement::Break(_, _) | Statement::Continue(_),
+                ..
+            })
+        )
+    }
+}
+
 impl<T> Default for Body<T>
 where
     T: Tag,
@@ -84,6 +128,8 @@ where
     T: Tag,
 {
     Assign(T::TypeTag, Box<LValue<T>>, Box<Expression<T>>),
+    // low..high or low..=high, with an optional step
+    Range(Box<OrExp<T>>, Box<OrExp<T>>, Option<Box<OrExp<T>>>, bool),
     OrExp(Box<OrExp<T>>),
 }
 
@@ -93,22 +139,237 @@ where
     T: Tag,
 {
     Variable(T::VariableID),
-    Index(Box<LValue<T>>, Box<Expression<T>>),
+    // tagged with where the assignment is, for an index out of range,
+    // and the type of the list or map being indexed
+    Index(FilePosition, T::TypeTag, Box<LValue<T>>, Box<Expression<T>>),
 }
 
-pub fn primary_to_lvalue<T>(primary: Primary<T>) -> Option<LValue<T>>
+pub fn primary_to_lvalue<T>(primary: Primary<T>, file_posn: FilePosition) -> Option<LValue<T>>
 where
     T: Tag,
 {
     match primary {
         Primary::Variable(var) => Some(LValue::Variable(var)),
-        Primary::Index(_, primary, expression) => {
-            primary_to_lvalue(*primary).map(|lvalue| LValue::Index(Box::new(lvalue), expression))
-        }
+        Primary::Index(typ, primary, expression) => primary_to_lvalue(*primary, file_posn)
+            .map(|lvalue| LValue::Index(file_posn, typ, Box::new(lvalue), expression)),
         _ => None,
     }
 }
 
+/*
+ * Replaces the escape sequences in a string or char literal
+ * \u{...} escapes are checked, so that literals are always valid UTF-8
+ */
+pub fn unescape(literal: &str) -> Result<String, &'static str> {
+    let mut string = String::new();
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            string.push(c);
+            continue;
+        }
+
+        string.push(match chars.next() {
+            Some('"') => '"',
+            Some('\'') => '\'',
+            Some('\\') => '\\',
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('u') => {
+                let code_point = chars
+                    .next()
+                    .filter(|&c| c == '{')
+                    .map(|_| chars.by_ref().take_while(|&c| c != '}').collect::<String>())
+                    .and_then(|hex| u32::from_str_radix(&hex, 16).ok());
+                // strings get passed to C, so they can't contain NUL
+                match code_point.filter(|&n| n != 0).and_then(std::char::from_u32) {
+                    Some(c) => c,
+                    None => return Err("invalid unicode escape"),
+                }
+            }
+            _ => return Err("invalid escape sequence"),
+        });
+    }
+    Ok(string)
+}
+
+pub enum StringPart<'a> {
+    Text(String),
+    // the byte offset of the code within the literal, the code, and its format spec
+    Code(usize, &'a str, Option<&'a str>),
+}
+
+/*
+ * Splits a string literal into its text and its interpolated {expression:spec}s
+ * the text gets unescaped, and {{ and }} stand for literal braces
+ */
+pub fn split_interpolated(literal: &str) -> Result<Vec<StringPart<'_>>, &'static str> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = literal.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                text.push(c);
+                // \u{...} escapes also contain braces
+                let escape = chars.next().map(|(_, c)| c);
+                if let Some(c) = escape {
+                    text.push(c);
+                }
+                if escape == Some('u') && chars.peek().map(|&(_, c)| c) == Some('{') {
+                    for (_, c) in chars.by_ref() {
+                        text.push(c);
+                        if c == '}' {
+                            break;
+                        }
+                    }
+                }
+            }
+            '{' if chars.peek().map(|&(_, c)| c) == Some('{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek().map(|&(_, c)| c) == Some('}') => {
+                chars.next();
+                text.push('}');
+            }
+            '}' => return Err("unmatched } in string literal"),
+            '{' => {
+                let mut depth = 0;
+                let mut end = None;
+                while let Some((j, c)) = chars.next() {
+                    match c {
+                        '"' | '\'' => skip_literal(&mut chars, c),
+                        '{' => depth += 1,
+                        '}' if depth == 0 => {
+                            end = Some(j);
+                            break;
+                        }
+                        '}' => depth -= 1,
+                        _ => (),
+                    }
+                }
+                let end = match end {
+                    Some(end) => end,
+                    None => return Err("unmatched { in string literal"),
+                };
+                parts.push(StringPart::Text(unescape(&text)?));
+                text.clear();
+                let code = &literal[i + 1..end];
+                parts.push(match split_spec(code) {
+                    Some(colon) => {
+                        StringPart::Code(i + 1, &code[..colon], Some(&code[colon + 1..]))
+                    }
+                    None => StringPart::Code(i + 1, code, None),
+                });
+            }
+            _ => text.push(c),
+        }
+    }
+    parts.push(StringPart::Text(unescape(&text)?));
+    Ok(parts)
+}
+
+/*
+ * Skips to the end of a string or char literal, whose opening quote was just consumed
+ */
+fn skip_literal(chars: &mut impl Iterator<Item = (usize, char)>, quote: char) {
+    while let Some((_, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            c if c == quote => break,
+            _ => (),
+        }
+    }
+}
+
+/*
+ * Finds the : that starts the format spec of an interpolated expression,
+ * skipping any inside brackets (like slices) or string and char literals
+ */
+fn split_spec(code: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut chars = code.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            '"' | '\'' => skip_literal(&mut chars, c),
+            ':' if depth == 0 => return Some(i),
+            _ => (),
+        }
+    }
+    None
+}
+
+/*
+ * A format spec looks like [[fill]align][0][width][.precision][kind]
+ * where align is one of < ^ >, and kind is one of x X o b ?
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct FormatSpec {
+    pub fill: char,
+    pub align: Option<char>,
+    pub zero: bool,
+    pub width: u64,
+    pub precision: Option<u64>,
+    pub kind: Option<char>,
+}
+
+pub fn parse_format_spec(spec: &str) -> Result<FormatSpec, &'static str> {
+    let is_align = |c: Option<char>| matches!(c, Some('<') | Some('^') | Some('>'));
+    let mut format_spec = FormatSpec {
+        fill: ' ',
+        align: None,
+        zero: false,
+        width: 0,
+        precision: None,
+        kind: None,
+    };
+
+    let mut chars = spec.chars().peekable();
+    if is_align(spec.chars().nth(1)) {
+        format_spec.fill = chars.next().unwrap();
+        format_spec.align = chars.next();
+    } else if is_align(chars.peek().copied()) {
+        format_spec.align = chars.next();
+    }
+    if chars.peek() == Some(&'0') {
+        format_spec.zero = true;
+        chars.next();
+    }
+
+    let number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+        let mut digits = String::new();
+        while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+            digits.push(c);
+            chars.next();
+        }
+        digits.parse::<u64>().ok()
+    };
+    format_spec.width = number(&mut chars).unwrap_or(0);
+    if chars.peek() == Some(&'.') {
+        chars.next();
+        format_spec.precision = match number(&mut chars) {
+            Some(precision) => Some(precision),
+            None => return Err("expected a precision after . in format spec"),
+        };
+    }
+
+    format_spec.kind = match chars.next() {
+        Some(c @ 'x') | Some(c @ 'X') | Some(c @ 'o') | Some(c @ 'b') | Some(c @ '?') => Some(c),
+        Some(_) => return Err("invalid format spec"),
+        None => None,
+    };
+    match chars.next() {
+        Some(_) => Err("invalid format spec"),
+        None => Ok(format_spec),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum OrExp<T>
 where
@@ -141,8 +402,8 @@ pub enum CompOp<T>
 where
     T: Tag,
 {
-    Leq,
-    Lt,
+    Leq(T::TypeTag),
+    Lt(T::TypeTag),
     Eq(T::TypeTag),
 }
 
@@ -151,7 +412,7 @@ pub enum AddExp<T>
 where
     T: Tag,
 {
-    Add(AddOp<T>, Box<MulExp<T>>, Box<AddExp<T>>),
+    Add(AddOp<T>, Box<AddExp<T>>, Box<MulExp<T>>),
     MulExp(Box<MulExp<T>>),
 }
 
@@ -161,7 +422,10 @@ where
     T: Tag,
 {
     Sum(T::TypeTag),
-    Difference,
+    Difference(T::TypeTag),
+    // set union, or bitwise or on integers
+    Union(T::TypeTag),
+    Xor(T::TypeTag),
 }
 
 #[derive(Debug, Clone)]
@@ -169,15 +433,22 @@ pub enum MulExp<T>
 where
     T: Tag,
 {
-    Mul(MulOp, Box<Unary<T>>, Box<MulExp<T>>),
+    Mul(MulOp<T>, Box<MulExp<T>>, Box<Unary<T>>),
     Unary(Box<Unary<T>>),
 }
 
 #[derive(Debug, Copy, Clone)]
-pub enum MulOp {
-    Product,
-    Quotient,
-    Remainder,
+pub enum MulOp<T>
+where
+    T: Tag,
+{
+    Product(T::TypeTag),
+    Quotient(T::TypeTag),
+    Remainder(T::TypeTag),
+    // set intersection, or bitwise and on integers
+    Intersection(T::TypeTag),
+    ShiftLeft(T::TypeTag),
+    ShiftRight(T::TypeTag),
 }
 
 #[derive(Debug, Clone)]
@@ -185,7 +456,7 @@ pub enum Unary<T>
 where
     T: Tag,
 {
-    Negate(Box<Unary<T>>),
+    Negate(T::TypeTag, Box<Unary<T>>),
     Not(Box<Unary<T>>),
     Primary(Box<Primary<T>>),
 }
@@ -197,15 +468,34 @@ where
 {
     Paren(Box<Expression<T>>),
     IntLit(u64), // I only parse positive integer btw
+    FloatLit(f64),
     StringLit(T::StringID),
     BoolLit(bool),
+    CharLit(char),
     Variable(T::VariableID),
     IfExp(IfExp<T>),
     ForExp(ForExp<T>),
+    ForInExp(ForInExp<T>),
+    // a for loop written in a list, with the number of for clauses it had,
+    // where each clause after the first is a loop at the end of the body of the one before it
+    Comprehension(usize, ForInExp<T>),
     StatementExp(Body<T>),
     Index(T::TypeTag, Box<Primary<T>>, Box<Expression<T>>),
+    Slice(
+        T::TypeTag,
+        Box<Primary<T>>,
+        Option<Box<Expression<T>>>,
+        Option<Box<Expression<T>>>,
+    ),
     Builtin(T::BuiltinID),
     List(T::DeclareTag, Vec<Expression<T>>),
+    Map(
+        T::DeclareTag,
+        T::DeclareTag,
+        Vec<(Expression<T>, Expression<T>)>,
+    ),
+    Set(T::DeclareTag, Vec<Expression<T>>),
+    Tuple(Vec<T::DeclareTag>, Vec<Expression<T>>),
     Function(Function<T>),
     FunCall(Box<Primary<T>>, Vec<Expression<T>>),
     Unit,
@@ -227,12 +517,29 @@ where
 {
     // once I add lists, this will build a list, hence the type tag
     pub tag: T::TypeTag,
+    pub label: Option<String>,
     pub init: Box<Statement<T>>,
     pub cond: Box<Expression<T>>,
     pub update: Box<Expression<T>>,
     pub body: Body<T>,
 }
 
+#[derive(Debug, Clone)]
+pub struct ForInExp<T>
+where
+    T: Tag,
+{
+    pub tag: T::TypeTag,
+    pub label: Option<String>,
+    // one variable gets each item, more than one destructure it as a tuple
+    pub vars: Vec<(T::TypeTag, T::VariableID)>,
+    // a list or range, since strings get turned into lists of chars
+    pub iterable: (T::TypeTag, Box<Expression<T>>),
+    pub body: Body<T>,
+    // whether the loop collects a list, which the loops builtins get lowered to don't
+    pub collect: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct IfExp<T>
 where
@@ -270,6 +577,45 @@ impl Tag for Parsed {
  * AST traits and implementations
  */
 
+impl<T> Expression<T>
+where
+    T: Tag,
+{
+    /*
+     * The primary this expression is made of, if there's nothing else to it
+     */
+    pub fn into_primary(self) -> Option<Primary<T>> {
+        let orexp = match self {
+            Expression::OrExp(orexp) => orexp,
+            _ => return None,
+        };
+        let andexp = match *orexp {
+            OrExp::AndExp(andexp) => andexp,
+            _ => return None,
+        };
+        let compexp = match *andexp {
+            AndExp::CompExp(compexp) => compexp,
+            _ => return None,
+        };
+        let addexp = match *compexp {
+            CompExp::AddExp(addexp) => addexp,
+            _ => return None,
+        };
+        let mulexp = match *addexp {
+            AddExp::MulExp(mulexp) => mulexp,
+            _ => return None,
+        };
+        let unary = match *mulexp {
+            MulExp::Unary(unary) => unary,
+            _ => return None,
+        };
+        match *unary {
+            Unary::Primary(primary) => Some(*primary),
+            _ => None,
+        }
+    }
+}
+
 pub trait ToExpression {
     type _Tag: Tag;
     fn to_expression(self) -> Expression<Self::_Tag>;
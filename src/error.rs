@@ -11,6 +11,19 @@ impl FilePosition {
     pub fn new() -> Self {
         FilePosition { line: 0, column: 0 }
     }
+
+    /*
+     * Lines and columns both start at 1
+     */
+    pub fn from_offset(source: &str, offset: usize) -> Self {
+        let before = &source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = match before.rfind('\n') {
+            Some(newline) => offset - newline,
+            None => offset + 1,
+        };
+        FilePosition { line, column }
+    }
 }
 
 impl Default for FilePosition {
@@ -6,16 +6,23 @@ use llvm_sys::ir_reader::*;
 use llvm_sys::linker::*;
 use llvm_sys::prelude::*;
 use llvm_sys::LLVMIntPredicate::*;
+use llvm_sys::LLVMRealPredicate::*;
+use std::ffi::CString;
 use std::ptr;
 
 const LLVM_FALSE: LLVMBool = 0;
 const LLVM_TRUE: LLVMBool = 1;
 
-const RTS_SOURCES: [&[u8]; 4] = [
+const RTS_SOURCES: [&[u8]; 9] = [
     include_bytes!("../rts/io.ll"),
     include_bytes!("../rts/rc.ll"),
     include_bytes!("../rts/strings.ll"),
     include_bytes!("../rts/lists.ll"),
+    include_bytes!("../rts/maps.ll"),
+    include_bytes!("../rts/tuples.ll"),
+    include_bytes!("../rts/ranges.ll"),
+    include_bytes!("../rts/panic.ll"),
+    include_bytes!("../rts/tests.ll"),
 ];
 
 macro_rules! nm {
@@ -39,6 +46,19 @@ unsafe fn load_rts_source(context: LLVMContextRef, code: &[u8]) -> LLVMModuleRef
     module
 }
 
+/*
+ * Where a break or continue out of a loop goes, and the lists it has to clean up on the way
+ */
+#[derive(Clone)]
+struct LoopBlocks {
+    label: Option<String>,
+    // the list the loop collects, if it does
+    list: Option<LLVMValueRef>,
+    iterable: Option<LLVMValueRef>,
+    break_bb: LLVMBasicBlockRef,
+    continue_bb: LLVMBasicBlockRef,
+}
+
 #[derive(Clone)]
 struct Builder {
     context: LLVMContextRef,
@@ -47,8 +67,7 @@ struct Builder {
     variables: Vec<LLVMValueRef>,
     strings: Vec<LLVMValueRef>,
     end: LLVMBasicBlockRef,
-    break_bb: LLVMBasicBlockRef,
-    continue_bb: LLVMBasicBlockRef,
+    loops: Vec<LoopBlocks>,
 }
 
 impl Builder {
@@ -65,9 +84,10 @@ impl Builder {
                 LLVMLinkModules2(module, rts_module);
             }
 
-            // NOTE: not sure if this is good
-            let void = LLVMVoidTypeInContext(context);
-            let function_type = LLVMFunctionType(void, ptr::null_mut(), 0, 0);
+            // int main(int argc, char **argv)
+            let int32 = LLVMInt32TypeInContext(context);
+            let argv = LLVMPointerType(LLVMPointerType(LLVMInt8TypeInContext(context), 0), 0);
+            let function_type = LLVMFunctionType(int32, [int32, argv].as_mut_ptr(), 2, 0);
             let main_fn = LLVMAddFunction(module, nm!("main"), function_type);
             let start = LLVMAppendBasicBlockInContext(context, main_fn, nm!("entry"));
 
@@ -75,13 +95,21 @@ impl Builder {
             // so I keep a block at the end, and then delete it after compilation
             let end = LLVMAppendBasicBlockInContext(context, main_fn, nm!("return"));
             LLVMPositionBuilderAtEnd(builder, end);
-            LLVMBuildRetVoid(builder);
+            LLVMBuildRet(builder, LLVMConstInt(int32, 0, LLVM_FALSE));
 
             LLVMPositionBuilderAtEnd(builder, start);
             // NOTE end not good
 
-            let break_bb = ptr::null_mut();
-            let continue_bb = ptr::null_mut();
+            // stash the arguments away for @args
+            LLVMBuildCall(
+                builder,
+                LLVMGetNamedFunction(module, nm!("init_args")),
+                [LLVMGetParam(main_fn, 0), LLVMGetParam(main_fn, 1)].as_mut_ptr(),
+                2,
+                nm!(""),
+            );
+
+            let loops = Vec::new();
 
             Builder {
                 context,
@@ -90,22 +118,15 @@ impl Builder {
                 variables,
                 strings,
                 end,
-                break_bb,
-                continue_bb,
+                loops,
             }
         }
     }
 
     unsafe fn declare_variable(&mut self, typ: &SwindleType) {
-        let llvm_type = match typ {
-            SwindleType::Int => self.int64_ty(),
-            SwindleType::Bool => self.int1_ty(),
-            SwindleType::Unit => self.int1_ty(),
-            SwindleType::List(_) | SwindleType::String => self.rc_ty(),
-        };
-        let var = LLVMBuildAlloca(self.builder, llvm_type, nm!("var"));
+        let var = LLVMBuildAlloca(self.builder, self.llvm_type(typ), nm!("var"));
         self.variables.push(var);
-        if let SwindleType::List(_) | SwindleType::String = typ {
+        if typ.is_rc() {
             let rc = LLVMBuildAlloca(
                 self.builder,
                 LLVMGetTypeByName(self.module, nm!("struct.RC")),
@@ -148,6 +169,53 @@ impl Builder {
         self.strings.push(rc);
     }
 
+    unsafe fn call(&self, func: *const i8, args: &mut [LLVMValueRef]) -> LLVMValueRef {
+        LLVMBuildCall(
+            self.builder,
+            LLVMGetNamedFunction(self.module, func),
+            args.as_mut_ptr(),
+            args.len() as u32,
+            nm!(""),
+        )
+    }
+
+    unsafe fn alloca_rc(&self, name: *const i8) -> LLVMValueRef {
+        LLVMBuildAlloca(
+            self.builder,
+            LLVMGetTypeByName(self.module, nm!("struct.RC")),
+            name,
+        )
+    }
+
+    /*
+     * Code after a break or continue never runs, but it still needs a block to go in
+     */
+    unsafe fn unreachable_block(&self) {
+        let current_block = LLVMGetInsertBlock(self.builder);
+        let next_block = LLVMGetNextBasicBlock(current_block);
+        let block = LLVMInsertBasicBlockInContext(self.context, next_block, nm!("unreachable"));
+        LLVMPositionBuilderAtEnd(self.builder, block);
+    }
+
+    /*
+     * Finds the loop a break or continue goes to, freeing the lists of any loops inside of it
+     */
+    unsafe fn jump_out(&self, label: &Option<String>) -> LoopBlocks {
+        let depth = match label {
+            Some(_) => self.loops.iter().rposition(|l| &l.label == label).unwrap(),
+            None => self.loops.len() - 1,
+        };
+        for inner in &self.loops[depth + 1..] {
+            if let Some(iterable) = inner.iterable {
+                self.call(nm!("drop"), &mut [iterable]);
+            }
+            if let Some(list) = inner.list {
+                self.call(nm!("destroy_noref"), &mut [list]);
+            }
+        }
+        self.loops[depth].clone()
+    }
+
     unsafe fn const_int(&self, n: u64) -> LLVMValueRef {
         LLVMConstInt(self.int64_ty(), n, LLVM_TRUE)
     }
@@ -160,10 +228,169 @@ impl Builder {
         LLVMConstInt(self.int1_ty(), 0, LLVM_FALSE)
     }
 
+    unsafe fn llvm_type(&self, typ: &SwindleType) -> LLVMTypeRef {
+        match typ {
+            SwindleType::Int => self.int64_ty(),
+            SwindleType::Float => LLVMDoubleTypeInContext(self.context),
+            SwindleType::U8 => LLVMInt8TypeInContext(self.context),
+            SwindleType::I32 | SwindleType::U32 => self.int32_ty(),
+            SwindleType::U64 => self.int64_ty(),
+            SwindleType::Bool => self.int1_ty(),
+            SwindleType::Unit => self.int1_ty(),
+            SwindleType::Char => self.int32_ty(),
+            SwindleType::Tuple(types) if typ.is_unboxed() => {
+                let mut fields = types.iter().map(|t| self.llvm_type(t)).collect::<Vec<_>>();
+                LLVMStructTypeInContext(
+                    self.context,
+                    fields.as_mut_ptr(),
+                    fields.len() as u32,
+                    LLVM_FALSE,
+                )
+            }
+            // the start, stop and step, and whether the stop is included
+            SwindleType::Range => {
+                let int64 = self.int64_ty();
+                let mut fields = [int64, int64, int64, self.int1_ty()];
+                LLVMStructTypeInContext(self.context, fields.as_mut_ptr(), 4, LLVM_FALSE)
+            }
+            SwindleType::List(_)
+            | SwindleType::String
+            | SwindleType::Map(_, _)
+            | SwindleType::Set(_)
+            | SwindleType::Tuple(_) => self.rc_ty(),
+        }
+    }
+
+    /*
+     * The ItemType of a list holding typ
+     */
+    unsafe fn item_type(&self, typ: &SwindleType) -> LLVMValueRef {
+        LLVMConstInt(
+            self.int32_ty(),
+            match typ {
+                SwindleType::Int => 0,       // SW_INT
+                SwindleType::Bool => 1,      // SW_BOOL
+                SwindleType::Unit => 2,      // SW_UNIT
+                SwindleType::String => 3,    // SW_STRING
+                SwindleType::List(_) => 4,   // SW_LIST
+                SwindleType::Char => 5,      // SW_CHAR
+                SwindleType::Map(_, _) => 6, // SW_MAP
+                SwindleType::Set(_) => 7,    // SW_SET
+                SwindleType::Tuple(_) => 8,  // SW_TUPLE
+                SwindleType::Range => 9,     // SW_RANGE
+                SwindleType::Float => 10,    // SW_FLOAT
+                SwindleType::U8 => 11,       // SW_U8
+                SwindleType::I32 => 12,      // SW_I32
+                SwindleType::U32 => 13,      // SW_U32
+                SwindleType::U64 => 14,      // SW_U64
+            },
+            LLVM_FALSE,
+        )
+    }
+
+    /*
+     * The RTS keeps every tuple and range behind an RC, so an unboxed one gets boxed
+     * before it goes into a list or to the RTS, and unboxed when it comes back out.
+     * Similarly, narrow integers are widened to 64 bits and truncated again.
+     */
+    unsafe fn to_item(&self, value: LLVMValueRef, typ: &SwindleType) -> LLVMValueRef {
+        match typ {
+            SwindleType::Tuple(types) if typ.is_unboxed() => {
+                let rc = self.alloca_rc(nm!("tuple"));
+                let mut c_args = vec![rc, self.const_int(types.len() as u64)];
+                for (idx, typ) in types.iter().enumerate() {
+                    let item = LLVMBuildExtractValue(self.builder, value, idx as u32, nm!("item"));
+                    c_args.push(self.item_type(typ));
+                    c_args.push(self.to_item(item, typ));
+                }
+                self.call(nm!("rc_tuple"), &mut c_args);
+                rc
+            }
+            SwindleType::Range => {
+                let rc = self.alloca_rc(nm!("range"));
+                let mut c_args = vec![rc];
+                for idx in 0..4 {
+                    c_args.push(LLVMBuildExtractValue(self.builder, value, idx, nm!("item")));
+                }
+                self.call(nm!("rc_range"), &mut c_args);
+                rc
+            }
+            typ if typ.is_narrow() => {
+                let signed = if typ.is_unsigned() {
+                    LLVM_FALSE
+                } else {
+                    LLVM_TRUE
+                };
+                LLVMBuildIntCast2(self.builder, value, self.int64_ty(), signed, nm!("widen"))
+            }
+            _ => value,
+        }
+    }
+
+    unsafe fn unbox_item(&self, value: LLVMValueRef, typ: &SwindleType) -> LLVMValueRef {
+        match typ {
+            SwindleType::Tuple(types) if typ.is_unboxed() => {
+                let mut tuple = LLVMGetUndef(self.llvm_type(typ));
+                for (idx, typ) in types.iter().enumerate() {
+                    let idx = idx as u32;
+                    let item = self.call(
+                        nm!("index_tuple_"),
+                        &mut [value, self.const_int(idx as u64)],
+                    );
+                    let item = self.as_item(item, typ);
+                    tuple = LLVMBuildInsertValue(self.builder, tuple, item, idx, nm!("tuple"));
+                }
+                self.call(nm!("destroy_noref"), &mut [value]);
+                tuple
+            }
+            SwindleType::Range => {
+                let mut range = LLVMGetUndef(self.llvm_type(typ));
+                for idx in 0..4 {
+                    let mut item =
+                        self.call(nm!("index_range_"), &mut [value, self.const_int(idx)]);
+                    if idx == 3 {
+                        item = LLVMBuildTrunc(self.builder, item, self.int1_ty(), nm!("inclusive"));
+                    }
+                    range =
+                        LLVMBuildInsertValue(self.builder, range, item, idx as u32, nm!("range"));
+                }
+                self.call(nm!("destroy_noref"), &mut [value]);
+                range
+            }
+            typ if typ.is_narrow() => {
+                LLVMBuildTrunc(self.builder, value, self.llvm_type(typ), nm!("narrow"))
+            }
+            _ => value,
+        }
+    }
+
+    /*
+     * The value of typ held by a ListItem
+     */
+    unsafe fn as_item(&self, item: LLVMValueRef, typ: &SwindleType) -> LLVMValueRef {
+        let func = match typ {
+            SwindleType::Int
+            | SwindleType::U8
+            | SwindleType::I32
+            | SwindleType::U32
+            | SwindleType::U64 => nm!("as_int"),
+            SwindleType::Bool => nm!("as_bool"),
+            SwindleType::Unit => nm!("as_unit"),
+            SwindleType::Char => nm!("as_char"),
+            SwindleType::Float => nm!("as_float"),
+            _ => nm!("as_rc"),
+        };
+        self.unbox_item(self.call(func, &mut [item]), typ)
+    }
+
     unsafe fn int64_ty(&self) -> LLVMTypeRef {
         LLVMInt64TypeInContext(self.context)
     }
 
+    unsafe fn int32_ty(&self) -> LLVMTypeRef {
+        LLVMInt32TypeInContext(self.context)
+    }
+
     unsafe fn int1_ty(&self) -> LLVMTypeRef {
         LLVMInt1TypeInContext(self.context)
     }
@@ -171,6 +398,58 @@ impl Builder {
     unsafe fn rc_ty(&self) -> LLVMTypeRef {
         LLVMPointerType(LLVMGetTypeByName(self.module, nm!("struct.RC")), 0)
     }
+
+    /*
+     * Shifting an integer by its width or more is undefined in LLVM,
+     * so the amount wraps around instead
+     */
+    unsafe fn shift_amount(&self, amount: LLVMValueRef, typ: &SwindleType) -> LLVMValueRef {
+        let typ = self.llvm_type(typ);
+        let mask = LLVMConstInt(typ, LLVMGetIntTypeWidth(typ) as u64 - 1, LLVM_FALSE);
+        LLVMBuildAnd(self.builder, amount, mask, nm!("amount"))
+    }
+
+    /*
+     * Converting a float which doesn't fit to an integer is poison in LLVM,
+     * so NaN becomes 0 and anything out of range becomes the nearest int instead
+     */
+    unsafe fn float_to_int(&self, value: LLVMValueRef) -> LLVMValueRef {
+        let float = LLVMDoubleTypeInContext(self.context);
+        let limit = LLVMConstReal(float, 9_223_372_036_854_775_808.0);
+        let min = LLVMConstReal(float, -9_223_372_036_854_775_808.0);
+        let int = LLVMBuildFPToSI(self.builder, value, self.int64_ty(), nm!("int"));
+        let too_big = LLVMBuildFCmp(self.builder, LLVMRealOGE, value, limit, nm!("too_big"));
+        let too_small = LLVMBuildFCmp(self.builder, LLVMRealOLT, value, min, nm!("too_small"));
+        let nan = LLVMBuildFCmp(self.builder, LLVMRealUNO, value, value, nm!("nan"));
+        let int = LLVMBuildSelect(
+            self.builder,
+            too_big,
+            self.const_int(i64::MAX as u64),
+            int,
+            nm!("int"),
+        );
+        let int = LLVMBuildSelect(
+            self.builder,
+            too_small,
+            self.const_int(i64::MIN as u64),
+            int,
+            nm!("int"),
+        );
+        LLVMBuildSelect(self.builder, nan, self.const_int(0), int, nm!("int"))
+    }
+
+    /*
+     * A float between min and max, or NaN if it was NaN
+     */
+    unsafe fn clamp_float(&self, value: LLVMValueRef, min: f64, max: f64) -> LLVMValueRef {
+        let float = LLVMDoubleTypeInContext(self.context);
+        let min = LLVMConstReal(float, min);
+        let max = LLVMConstReal(float, max);
+        let too_small = LLVMBuildFCmp(self.builder, LLVMRealOLT, value, min, nm!("too_small"));
+        let value = LLVMBuildSelect(self.builder, too_small, min, value, nm!("clamped"));
+        let too_big = LLVMBuildFCmp(self.builder, LLVMRealOGT, value, max, nm!("too_big"));
+        LLVMBuildSelect(self.builder, too_big, max, value, nm!("clamped"))
+    }
 }
 
 impl Drop for Builder {
@@ -183,7 +462,12 @@ impl Drop for Builder {
     }
 }
 
-pub fn cg_program(program: Program<PCG>, var_info: Vec<SwindleType>, strings: Vec<String>) {
+pub fn cg_program(
+    program: Program<PCG>,
+    var_info: Vec<SwindleType>,
+    strings: Vec<String>,
+    testing: bool,
+) {
     unsafe {
         let mut builder = Builder::new();
         for typ in &var_info {
@@ -196,7 +480,7 @@ pub fn cg_program(program: Program<PCG>, var_info: Vec<SwindleType>, strings: Ve
             cg_tagged_statement(&mut builder, tagged_stmt);
         }
         for (idx, typ) in var_info.iter().enumerate() {
-            if let SwindleType::List(_) | SwindleType::String = typ {
+            if typ.is_rc() {
                 LLVMBuildCall(
                     builder.builder,
                     LLVMGetNamedFunction(builder.module, nm!("drop2")),
@@ -215,7 +499,13 @@ pub fn cg_program(program: Program<PCG>, var_info: Vec<SwindleType>, strings: Ve
                 nm!(""),
             );
         }
-        LLVMBuildRetVoid(builder.builder);
+        // reaching the end of the program means success, unless a test failed
+        let status = if testing {
+            builder.call(nm!("finish_tests"), &mut [])
+        } else {
+            LLVMConstInt(LLVMInt32TypeInContext(builder.context), 0, LLVM_FALSE)
+        };
+        LLVMBuildRet(builder.builder, status);
         LLVMDeleteBasicBlock(builder.end);
         LLVMDumpModule(builder.module);
     }
@@ -240,7 +530,7 @@ unsafe fn cg_tagged_statement(
 
 unsafe fn cg_statement(builder: &mut Builder, statement: Statement<PCG>) -> LLVMValueRef {
     match statement {
-        Statement::Declare(SwindleType::List(_) | SwindleType::String, id, expression) => {
+        Statement::Declare(typ, id, expression) if typ.is_rc() => {
             LLVMBuildCall(
                 builder.builder,
                 LLVMGetNamedFunction(builder.module, nm!("drop2")),
@@ -266,28 +556,68 @@ unsafe fn cg_statement(builder: &mut Builder, statement: Statement<PCG>) -> LLVM
             );
             builder.unit()
         }
-        Statement::Break => {
-            LLVMBuildBr(builder.builder, builder.break_bb);
+        Statement::Destructure(bindings, expression) => {
+            let tuple = cg_expression(builder, *expression);
+            cg_destructure(builder, bindings, tuple);
+            builder.unit()
+        }
+        Statement::Break(label, value) => {
+            let value = value.map(|(typ, value)| {
+                let value = cg_expression(builder, *value);
+                builder.to_item(value, &typ)
+            });
+            let target = builder.jump_out(&label);
+            // only loops which collect a list can be broken out of with a value
+            if let Some(value) = value {
+                builder.call(nm!("push_"), &mut [target.list.unwrap(), value]);
+            }
+            LLVMBuildBr(builder.builder, target.break_bb);
+            builder.unreachable_block();
             builder.unit()
         }
-        Statement::Continue => {
-            LLVMBuildBr(builder.builder, builder.continue_bb);
+        Statement::Continue(label) => {
+            let target = builder.jump_out(&label);
+            LLVMBuildBr(builder.builder, target.continue_bb);
+            builder.unreachable_block();
             builder.unit()
         }
         Statement::Expression(expression) => cg_expression(builder, *expression),
+        Statement::Test(mut name, body) => {
+            // begin_test forks, and only returns true in the child, which runs the body
+            name.push('\0');
+            let name =
+                LLVMBuildGlobalStringPtr(builder.builder, name.as_ptr() as *const i8, nm!("test"));
+            let current_block = LLVMGetInsertBlock(builder.builder);
+            let next_block = LLVMGetNextBasicBlock(current_block);
+            let then = LLVMInsertBasicBlockInContext(builder.context, next_block, nm!("then"));
+            let finally =
+                LLVMInsertBasicBlockInContext(builder.context, next_block, nm!("finally"));
+            LLVMPositionBuilderAtEnd(builder.builder, current_block);
+            let in_child = builder.call(nm!("begin_test"), &mut [name]);
+            LLVMBuildCondBr(builder.builder, in_child, then, finally);
+            LLVMPositionBuilderAtEnd(builder.builder, then);
+            cg_body(builder, body);
+            builder.call(nm!("end_test"), &mut []);
+            LLVMBuildBr(builder.builder, finally);
+            LLVMPositionBuilderAtEnd(builder.builder, finally);
+            builder.unit()
+        }
     }
 }
 
 unsafe fn cg_expression(builder: &mut Builder, expression: Expression<PCG>) -> LLVMValueRef {
     match expression {
         Expression::Assign(typ, box LValue::Variable(id), expression) => {
-            let is_rc = if let SwindleType::List(_) | SwindleType::String = typ {
-                true
-            } else {
-                false
-            };
             let var = builder.variables[id];
-            let expression = if is_rc {
+            let expression = if typ.is_rc() {
+                // the new value might be made from the old one, so it can't be dropped until after
+                let rc = LLVMBuildCall(
+                    builder.builder,
+                    LLVMGetNamedFunction(builder.module, nm!("alloc")),
+                    [cg_expression(builder, *expression)].as_mut_ptr(),
+                    1,
+                    nm!("rc"),
+                );
                 LLVMBuildCall(
                     builder.builder,
                     LLVMGetNamedFunction(builder.module, nm!("drop2")),
@@ -295,32 +625,49 @@ unsafe fn cg_expression(builder: &mut Builder, expression: Expression<PCG>) -> L
                     1,
                     nm!(""),
                 );
-                LLVMBuildCall(
-                    builder.builder,
-                    LLVMGetNamedFunction(builder.module, nm!("alloc")),
-                    [cg_expression(builder, *expression)].as_mut_ptr(),
-                    1,
-                    nm!("rc"),
-                )
+                rc
             } else {
                 cg_expression(builder, *expression)
             };
             LLVMBuildStore(builder.builder, expression, var);
             expression
         }
-        Expression::Assign(_, box LValue::Index(lvalue, index), expression) => {
+        Expression::Assign(_, box LValue::Index(file_posn, typ, lvalue, index), expression) => {
             let lvalue = cg_lvalue(builder, *lvalue);
             let index = cg_expression(builder, *index);
             let expression = cg_expression(builder, *expression);
-            LLVMBuildCall(
-                builder.builder,
-                LLVMGetNamedFunction(builder.module, nm!("set_")),
-                [lvalue, index, expression].as_mut_ptr(),
-                3,
-                nm!(""),
-            );
+            match &typ {
+                SwindleType::Map(key, value) => {
+                    let key = builder.to_item(index, key);
+                    let item = builder.to_item(expression, value);
+                    builder.call(nm!("set_map_"), &mut [lvalue, key, item]);
+                }
+                SwindleType::List(item) => {
+                    let item = builder.to_item(expression, item);
+                    let line = builder.const_int(file_posn.line as u64);
+                    let column = builder.const_int(file_posn.column as u64);
+                    builder.call(nm!("set_"), &mut [lvalue, line, column, index, item]);
+                }
+                _ => panic!("this shouldn't happen"),
+            };
             expression
         }
+        Expression::Range(low, high, step, inclusive) => {
+            let low = cg_orexp(builder, *low);
+            let high = cg_orexp(builder, *high);
+            let step = match step {
+                Some(step) => cg_orexp(builder, *step),
+                None => builder.const_int(1),
+            };
+            // low..=high keeps high as its stop, with a flag saying it's included
+            let inclusive = builder.const_bool(inclusive);
+            let mut range = LLVMGetUndef(builder.llvm_type(&SwindleType::Range));
+            for (idx, field) in [low, high, step, inclusive].iter().enumerate() {
+                range =
+                    LLVMBuildInsertValue(builder.builder, range, *field, idx as u32, nm!("range"));
+            }
+            range
+        }
         Expression::OrExp(orexp) => cg_orexp(builder, *orexp),
     }
 }
@@ -328,16 +675,20 @@ unsafe fn cg_expression(builder: &mut Builder, expression: Expression<PCG>) -> L
 unsafe fn cg_lvalue(builder: &mut Builder, lvalue: LValue<PCG>) -> LLVMValueRef {
     match lvalue {
         LValue::Variable(id) => LLVMBuildLoad(builder.builder, builder.variables[id], nm!("lv")),
-        LValue::Index(lvalue, index) => {
+        LValue::Index(file_posn, typ, lvalue, index) => {
             let lvalue = cg_lvalue(builder, *lvalue);
             let index = cg_expression(builder, *index);
-            LLVMBuildCall(
-                builder.builder,
-                LLVMGetNamedFunction(builder.module, nm!("get_setter_")),
-                [lvalue, index].as_mut_ptr(),
-                2,
-                nm!("index"),
-            )
+            match &typ {
+                SwindleType::Map(key, _) => {
+                    let key = builder.to_item(index, key);
+                    builder.call(nm!("get_map_setter_"), &mut [lvalue, key])
+                }
+                _ => {
+                    let line = builder.const_int(file_posn.line as u64);
+                    let column = builder.const_int(file_posn.column as u64);
+                    builder.call(nm!("get_setter_"), &mut [lvalue, line, column, index])
+                }
+            }
         }
     }
 }
@@ -368,34 +719,70 @@ unsafe fn cg_andexp(builder: &mut Builder, andexp: AndExp<PCG>) -> LLVMValueRef
 
 unsafe fn cg_compexp(builder: &mut Builder, compexp: CompExp<PCG>) -> LLVMValueRef {
     match compexp {
-        CompExp::Comp(CompOp::Eq(SwindleType::String), addexp1, addexp2) => {
+        CompExp::Comp(
+            op @ (CompOp::Leq(SwindleType::String | SwindleType::List(_) | SwindleType::Tuple(_))
+            | CompOp::Lt(SwindleType::String | SwindleType::List(_) | SwindleType::Tuple(_))
+            | CompOp::Eq(
+                SwindleType::String
+                | SwindleType::List(_)
+                | SwindleType::Tuple(_)
+                | SwindleType::Range,
+            )),
+            addexp1,
+            addexp2,
+        ) => {
             let addexp1 = cg_addexp(builder, *addexp1);
             let addexp2 = cg_addexp(builder, *addexp2);
+            let function = match &op {
+                CompOp::Leq(SwindleType::String) => nm!("strleq"),
+                CompOp::Lt(SwindleType::String) => nm!("strlt"),
+                CompOp::Eq(SwindleType::String) => nm!("streq"),
+                CompOp::Leq(SwindleType::Tuple(_)) => nm!("tupleleq"),
+                CompOp::Lt(SwindleType::Tuple(_)) => nm!("tuplelt"),
+                CompOp::Eq(SwindleType::Tuple(_)) => nm!("tupleeq"),
+                CompOp::Eq(SwindleType::Range) => nm!("rangeeq"),
+                CompOp::Leq(_) => nm!("listleq"),
+                CompOp::Lt(_) => nm!("listlt"),
+                CompOp::Eq(_) => nm!("listeq"),
+            };
+            let (addexp1, addexp2) = match op {
+                CompOp::Leq(typ) | CompOp::Lt(typ) | CompOp::Eq(typ) => (
+                    builder.to_item(addexp1, &typ),
+                    builder.to_item(addexp2, &typ),
+                ),
+            };
             LLVMBuildCall(
                 builder.builder,
-                LLVMGetNamedFunction(builder.module, nm!("streq")),
+                LLVMGetNamedFunction(builder.module, function),
                 [addexp1, addexp2].as_mut_ptr(),
                 2,
                 nm!(""),
             )
         }
-        CompExp::Comp(CompOp::Eq(SwindleType::List(_)), addexp1, addexp2) => {
+        CompExp::Comp(
+            op @ (CompOp::Leq(SwindleType::Float)
+            | CompOp::Lt(SwindleType::Float)
+            | CompOp::Eq(SwindleType::Float)),
+            addexp1,
+            addexp2,
+        ) => {
             let addexp1 = cg_addexp(builder, *addexp1);
             let addexp2 = cg_addexp(builder, *addexp2);
-            LLVMBuildCall(
-                builder.builder,
-                LLVMGetNamedFunction(builder.module, nm!("listeq")),
-                [addexp1, addexp2].as_mut_ptr(),
-                2,
-                nm!(""),
-            )
+            let (pred, name) = match op {
+                CompOp::Leq(_) => (LLVMRealOLE, nm!("leq")),
+                CompOp::Lt(_) => (LLVMRealOLT, nm!("lt")),
+                CompOp::Eq(_) => (LLVMRealOEQ, nm!("eq")),
+            };
+            LLVMBuildFCmp(builder.builder, pred, addexp1, addexp2, name)
         }
         CompExp::Comp(op, addexp1, addexp2) => {
             let addexp1 = cg_addexp(builder, *addexp1);
             let addexp2 = cg_addexp(builder, *addexp2);
             let (pred, name) = match op {
-                CompOp::Leq => (LLVMIntSLE, nm!("leq")),
-                CompOp::Lt => (LLVMIntSLT, nm!("lt")),
+                CompOp::Leq(typ) if typ.is_unsigned() => (LLVMIntULE, nm!("leq")),
+                CompOp::Lt(typ) if typ.is_unsigned() => (LLVMIntULT, nm!("lt")),
+                CompOp::Leq(_) => (LLVMIntSLE, nm!("leq")),
+                CompOp::Lt(_) => (LLVMIntSLT, nm!("lt")),
                 CompOp::Eq(_) => (LLVMIntEQ, nm!("eq")),
             };
             LLVMBuildICmp(builder.builder, pred, addexp1, addexp2, name)
@@ -406,9 +793,9 @@ unsafe fn cg_compexp(builder: &mut Builder, compexp: CompExp<PCG>) -> LLVMValueR
 
 unsafe fn cg_addexp(builder: &mut Builder, addexp: AddExp<PCG>) -> LLVMValueRef {
     match addexp {
-        AddExp::Add(op, mulexp, addexp) => {
-            let mulexp = cg_mulexp(builder, *mulexp);
+        AddExp::Add(op, addexp, mulexp) => {
             let addexp = cg_addexp(builder, *addexp);
+            let mulexp = cg_mulexp(builder, *mulexp);
             match op {
                 AddOp::Sum(SwindleType::String) => {
                     let rc = LLVMBuildAlloca(
@@ -419,19 +806,42 @@ unsafe fn cg_addexp(builder: &mut Builder, addexp: AddExp<PCG>) -> LLVMValueRef
                     LLVMBuildCall(
                         builder.builder,
                         LLVMGetNamedFunction(builder.module, nm!("append")),
-                        [rc, mulexp, addexp].as_mut_ptr(),
+                        [rc, addexp, mulexp].as_mut_ptr(),
                         3,
                         nm!(""),
                     );
                     rc
                 }
-                AddOp::Sum(SwindleType::Int) => {
-                    LLVMBuildAdd(builder.builder, mulexp, addexp, nm!("sum"))
+                AddOp::Sum(SwindleType::List(_)) => {
+                    let rc = builder.alloca_rc(nm!("rc"));
+                    builder.call(nm!("concat_list"), &mut [rc, addexp, mulexp]);
+                    rc
+                }
+                AddOp::Sum(typ) if typ.is_integer() => {
+                    LLVMBuildAdd(builder.builder, addexp, mulexp, nm!("sum"))
+                }
+                AddOp::Sum(SwindleType::Float) => {
+                    LLVMBuildFAdd(builder.builder, addexp, mulexp, nm!("sum"))
                 }
                 AddOp::Sum(_) => panic!("this should be impossible"),
-                AddOp::Difference => {
-                    LLVMBuildSub(builder.builder, mulexp, addexp, nm!("difference"))
+                AddOp::Difference(SwindleType::Set(_)) => {
+                    let rc = builder.alloca_rc(nm!("rc"));
+                    builder.call(nm!("set_difference"), &mut [rc, addexp, mulexp]);
+                    rc
+                }
+                AddOp::Difference(SwindleType::Float) => {
+                    LLVMBuildFSub(builder.builder, addexp, mulexp, nm!("difference"))
+                }
+                AddOp::Difference(_) => {
+                    LLVMBuildSub(builder.builder, addexp, mulexp, nm!("difference"))
+                }
+                AddOp::Union(SwindleType::Set(_)) => {
+                    let rc = builder.alloca_rc(nm!("rc"));
+                    builder.call(nm!("set_union"), &mut [rc, addexp, mulexp]);
+                    rc
                 }
+                AddOp::Union(_) => LLVMBuildOr(builder.builder, addexp, mulexp, nm!("or")),
+                AddOp::Xor(_) => LLVMBuildXor(builder.builder, addexp, mulexp, nm!("xor")),
             }
         }
         AddExp::MulExp(mulexp) => cg_mulexp(builder, *mulexp),
@@ -440,13 +850,60 @@ unsafe fn cg_addexp(builder: &mut Builder, addexp: AddExp<PCG>) -> LLVMValueRef
 
 unsafe fn cg_mulexp(builder: &mut Builder, mulexp: MulExp<PCG>) -> LLVMValueRef {
     match mulexp {
-        MulExp::Mul(op, unary, mulexp) => {
-            let unary = cg_unary(builder, *unary);
+        MulExp::Mul(op, mulexp, unary) => {
             let mulexp = cg_mulexp(builder, *mulexp);
+            let unary = cg_unary(builder, *unary);
             match op {
-                MulOp::Product => LLVMBuildMul(builder.builder, unary, mulexp, nm!("product")),
-                MulOp::Quotient => LLVMBuildSDiv(builder.builder, unary, mulexp, nm!("quotient")),
-                MulOp::Remainder => LLVMBuildSRem(builder.builder, unary, mulexp, nm!("remainder")),
+                MulOp::Product(SwindleType::String) => {
+                    let rc = builder.alloca_rc(nm!("rc"));
+                    builder.call(nm!("repeat_string"), &mut [rc, mulexp, unary]);
+                    rc
+                }
+                MulOp::Product(SwindleType::List(_)) => {
+                    let rc = builder.alloca_rc(nm!("rc"));
+                    builder.call(nm!("repeat_list"), &mut [rc, mulexp, unary]);
+                    rc
+                }
+                MulOp::Product(SwindleType::Float) => {
+                    LLVMBuildFMul(builder.builder, mulexp, unary, nm!("product"))
+                }
+                MulOp::Product(_) => LLVMBuildMul(builder.builder, mulexp, unary, nm!("product")),
+                MulOp::Quotient(SwindleType::Float) => {
+                    LLVMBuildFDiv(builder.builder, mulexp, unary, nm!("quotient"))
+                }
+                MulOp::Quotient(typ) if typ.is_unsigned() => {
+                    LLVMBuildUDiv(builder.builder, mulexp, unary, nm!("quotient"))
+                }
+                MulOp::Quotient(_) => {
+                    LLVMBuildSDiv(builder.builder, mulexp, unary, nm!("quotient"))
+                }
+                MulOp::Remainder(SwindleType::Float) => {
+                    LLVMBuildFRem(builder.builder, mulexp, unary, nm!("remainder"))
+                }
+                MulOp::Remainder(typ) if typ.is_unsigned() => {
+                    LLVMBuildURem(builder.builder, mulexp, unary, nm!("remainder"))
+                }
+                MulOp::Remainder(_) => {
+                    LLVMBuildSRem(builder.builder, mulexp, unary, nm!("remainder"))
+                }
+                MulOp::Intersection(SwindleType::Set(_)) => {
+                    let rc = builder.alloca_rc(nm!("rc"));
+                    builder.call(nm!("set_intersection"), &mut [rc, mulexp, unary]);
+                    rc
+                }
+                MulOp::Intersection(_) => LLVMBuildAnd(builder.builder, mulexp, unary, nm!("and")),
+                MulOp::ShiftLeft(typ) => {
+                    let amount = builder.shift_amount(unary, &typ);
+                    LLVMBuildShl(builder.builder, mulexp, amount, nm!("shl"))
+                }
+                MulOp::ShiftRight(typ) if typ.is_unsigned() => {
+                    let amount = builder.shift_amount(unary, &typ);
+                    LLVMBuildLShr(builder.builder, mulexp, amount, nm!("shr"))
+                }
+                MulOp::ShiftRight(typ) => {
+                    let amount = builder.shift_amount(unary, &typ);
+                    LLVMBuildAShr(builder.builder, mulexp, amount, nm!("shr"))
+                }
             }
         }
         MulExp::Unary(unary) => cg_unary(builder, *unary),
@@ -455,7 +912,10 @@ unsafe fn cg_mulexp(builder: &mut Builder, mulexp: MulExp<PCG>) -> LLVMValueRef
 
 unsafe fn cg_unary(builder: &mut Builder, unary: Unary<PCG>) -> LLVMValueRef {
     match unary {
-        Unary::Negate(unary) => {
+        Unary::Negate(SwindleType::Float, unary) => {
+            LLVMBuildFNeg(builder.builder, cg_unary(builder, *unary), nm!("negate"))
+        }
+        Unary::Negate(_, unary) => {
             LLVMBuildNeg(builder.builder, cg_unary(builder, *unary), nm!("negate"))
         }
         Unary::Not(unary) => LLVMBuildNot(builder.builder, cg_unary(builder, *unary), nm!("not")),
@@ -467,67 +927,42 @@ unsafe fn cg_primary(builder: &mut Builder, primary: Primary<PCG>) -> LLVMValueR
     match primary {
         Primary::Paren(e) => cg_expression(builder, *e),
         Primary::IntLit(n) => builder.const_int(n),
+        Primary::FloatLit(f) => LLVMConstReal(LLVMDoubleTypeInContext(builder.context), f),
         Primary::StringLit(id) => builder.strings[id],
         Primary::BoolLit(b) => builder.const_bool(b),
+        Primary::CharLit(c) => LLVMConstInt(builder.int32_ty(), c as u64, LLVM_FALSE),
         Primary::Variable(id) => {
             LLVMBuildLoad(builder.builder, builder.variables[id], nm!("variable"))
         }
         Primary::IfExp(ifexp) => cg_ifexp(builder, ifexp),
         Primary::ForExp(forexp) => cg_forexp(builder, forexp),
+        Primary::ForInExp(forin) | Primary::Comprehension(_, forin) => cg_forinexp(builder, forin),
         Primary::StatementExp(body) => cg_body(builder, body),
-        Primary::Index(SwindleType::String, string, index) => {
-            let string = cg_primary(builder, *string);
-            let index = cg_expression(builder, *index);
-            let rc = LLVMBuildAlloca(
-                builder.builder,
-                LLVMGetTypeByName(builder.module, nm!("struct.RC")),
-                nm!("rc"),
-            );
-            LLVMBuildCall(
-                builder.builder,
-                LLVMGetNamedFunction(builder.module, nm!("index_string1")),
-                [rc, string, index].as_mut_ptr(),
-                3,
-                nm!(""),
-            );
-            rc
-        }
-        Primary::Index(SwindleType::List(typ), list, index) => {
-            let typ = *typ;
+        // the typechecker turns indexing into a builtin, which panics if it's out of range
+        Primary::Index(_, _, _) => panic!("this shouldn't happen"),
+        Primary::Slice(typ, list, low, high) => {
             let list = cg_primary(builder, *list);
-            let index = cg_expression(builder, *index);
-            let item = LLVMBuildCall(
-                builder.builder,
-                LLVMGetNamedFunction(builder.module, nm!("index_list")),
-                [list, index].as_mut_ptr(),
-                2,
-                nm!(""),
-            );
-            let func = LLVMGetNamedFunction(
-                builder.module,
-                match typ {
-                    SwindleType::Int => nm!("as_int"),
-                    SwindleType::Bool => nm!("as_bool"),
-                    SwindleType::Unit => nm!("as_unit"),
-                    SwindleType::List(_) | SwindleType::String => nm!("as_rc"),
-                },
-            );
-            LLVMBuildCall(builder.builder, func, [item].as_mut_ptr(), 1, nm!("item"))
+            // a missing bound covers the rest of the list, since slices get clamped
+            let low = match low {
+                Some(low) => cg_expression(builder, *low),
+                None => builder.const_int(0),
+            };
+            let high = match high {
+                Some(high) => cg_expression(builder, *high),
+                None => builder.const_int(i64::MAX as u64),
+            };
+            let rc = builder.alloca_rc(nm!("slice"));
+            let func = match typ {
+                SwindleType::String => nm!("index_string2"),
+                SwindleType::List(_) => nm!("slice_list"),
+                _ => panic!("this shouldn't happen"),
+            };
+            builder.call(func, &mut [rc, list, low, high]);
+            rc
         }
-        Primary::Index(_, _, _) => panic!("this shouldn't happen"),
         Primary::Builtin(builtin) => cg_builtin(builder, builtin),
         Primary::List(typ, items) => {
-            let item_type = LLVMConstInt(
-                LLVMInt32TypeInContext(builder.context),
-                match typ {
-                    SwindleType::Int => 0,     // SW_INT
-                    SwindleType::Bool => 1,    // SW_BOOL
-                    SwindleType::Unit => 2,    // SW_UNIT
-                    SwindleType::String => 3,  // SW_STRING
-                    SwindleType::List(_) => 4, // SW_LIST
-                },
-                LLVM_FALSE,
-            );
+            let item_type = builder.item_type(&typ);
             let rc = LLVMBuildAlloca(
                 builder.builder,
                 LLVMGetTypeByName(builder.module, nm!("struct.RC")),
@@ -536,7 +971,8 @@ unsafe fn cg_primary(builder: &mut Builder, primary: Primary<PCG>) -> LLVMValueR
 
             let mut c_args = vec![rc, item_type, builder.const_int(items.len() as u64)];
             for item in items {
-                c_args.push(cg_expression(builder, item));
+                let item = cg_expression(builder, item);
+                c_args.push(builder.to_item(item, &typ));
             }
             let num_args = c_args.len();
             LLVMBuildCall(
@@ -548,6 +984,58 @@ unsafe fn cg_primary(builder: &mut Builder, primary: Primary<PCG>) -> LLVMValueR
             );
             rc
         }
+        Primary::Map(key, value, entries) => {
+            let rc = builder.alloca_rc(nm!("map"));
+            let mut c_args = vec![
+                rc,
+                builder.item_type(&key),
+                builder.item_type(&value),
+                builder.const_int(entries.len() as u64),
+            ];
+            for (k, v) in entries {
+                let k = cg_expression(builder, k);
+                c_args.push(builder.to_item(k, &key));
+                let v = cg_expression(builder, v);
+                c_args.push(builder.to_item(v, &value));
+            }
+            builder.call(nm!("rc_map"), &mut c_args);
+            rc
+        }
+        Primary::Set(typ, items) => {
+            let rc = builder.alloca_rc(nm!("set"));
+            let mut c_args = vec![
+                rc,
+                builder.item_type(&typ),
+                builder.const_int(items.len() as u64),
+            ];
+            for item in items {
+                let item = cg_expression(builder, item);
+                c_args.push(builder.to_item(item, &typ));
+            }
+            builder.call(nm!("rc_set"), &mut c_args);
+            rc
+        }
+        Primary::Tuple(types, items) if SwindleType::Tuple(types.clone()).is_unboxed() => {
+            let typ = builder.llvm_type(&SwindleType::Tuple(types));
+            let mut tuple = LLVMGetUndef(typ);
+            for (idx, item) in items.into_iter().enumerate() {
+                let item = cg_expression(builder, item);
+                tuple =
+                    LLVMBuildInsertValue(builder.builder, tuple, item, idx as u32, nm!("tuple"));
+            }
+            tuple
+        }
+        Primary::Tuple(types, items) => {
+            let rc = builder.alloca_rc(nm!("tuple"));
+            let mut c_args = vec![rc, builder.const_int(items.len() as u64)];
+            for (item, typ) in items.into_iter().zip(&types) {
+                let item = cg_expression(builder, item);
+                c_args.push(builder.item_type(typ));
+                c_args.push(builder.to_item(item, typ));
+            }
+            builder.call(nm!("rc_tuple"), &mut c_args);
+            rc
+        }
         Primary::Function(_) => unimplemented!(),
         Primary::FunCall(_, _) => unimplemented!(),
         Primary::Unit => builder.unit(),
@@ -561,6 +1049,8 @@ unsafe fn cg_builtin(builder: &mut Builder, builtin: Builtin<PCG>) -> LLVMValueR
             let func = match typ {
                 SwindleType::String => nm!("length_string"),
                 SwindleType::List(_) => nm!("length_list"),
+                // sets are maps underneath
+                SwindleType::Map(_, _) | SwindleType::Set(_) => nm!("length_map"),
                 _ => panic!("this shouldn't be possible"),
             };
             LLVMBuildCall(
@@ -577,13 +1067,24 @@ unsafe fn cg_builtin(builder: &mut Builder, builtin: Builtin<PCG>) -> LLVMValueR
                     builder.module,
                     match typ {
                         SwindleType::Int => nm!("print_int"),
+                        SwindleType::Float => nm!("print_float"),
+                        SwindleType::U8 | SwindleType::I32 | SwindleType::U32 => {
+                            nm!("print_int")
+                        }
+                        SwindleType::U64 => nm!("print_u64"),
                         SwindleType::String => nm!("print_string"),
                         SwindleType::Bool => nm!("print_bool"),
                         SwindleType::Unit => nm!("print_unit"),
+                        SwindleType::Char => nm!("print_char"),
                         SwindleType::List(_) => nm!("print_list"),
+                        SwindleType::Map(_, _) => nm!("print_map"),
+                        SwindleType::Set(_) => nm!("print_set"),
+                        SwindleType::Tuple(_) => nm!("print_tuple"),
+                        SwindleType::Range => nm!("print_range"),
                     },
                 );
                 let arg = cg_expression(builder, arg);
+                let arg = builder.to_item(arg, &typ);
                 LLVMBuildCall(builder.builder, print_fn, [arg].as_mut_ptr(), 1, nm!(""));
             }
 
@@ -598,36 +1099,166 @@ unsafe fn cg_builtin(builder: &mut Builder, builtin: Builtin<PCG>) -> LLVMValueR
             }
             builder.unit()
         }
+        Builtin::Assert(file_posn, expression, message) => {
+            let expression = cg_expression(builder, *expression);
+            let message = match message {
+                Some(message) => cg_expression(builder, *message),
+                None => LLVMConstPointerNull(builder.rc_ty()),
+            };
+            let line = builder.const_int(file_posn.line as u64);
+            let column = builder.const_int(file_posn.column as u64);
+            builder.call(nm!("assert_"), &mut [expression, line, column, message]);
+            builder.unit()
+        }
+        Builtin::Panic(file_posn, message) => {
+            let message = cg_expression(builder, *message);
+            let line = builder.const_int(file_posn.line as u64);
+            let column = builder.const_int(file_posn.column as u64);
+            builder.call(nm!("panic_"), &mut [line, column, message]);
+            builder.unit()
+        }
+        Builtin::Runtime(func, args, typ) => cg_runtime(builder, func, &[], args, typ),
+        Builtin::Checked(file_posn, func, args, typ) => {
+            let line = builder.const_int(file_posn.line as u64);
+            let column = builder.const_int(file_posn.column as u64);
+            cg_runtime(builder, func, &[line, column], args, typ)
+        }
+        Builtin::Index(file_posn, list_type, list, index) => {
+            let list = cg_primary(builder, *list);
+            let index = cg_expression(builder, *index);
+            let line = builder.const_int(file_posn.line as u64);
+            let column = builder.const_int(file_posn.column as u64);
+            let (func, index, typ) = match list_type {
+                SwindleType::Map(key, value) => {
+                    (nm!("index_map"), builder.to_item(index, &key), *value)
+                }
+                SwindleType::List(typ) => (nm!("index_list"), index, *typ),
+                _ => unreachable!(),
+            };
+            let item = builder.call(func, &mut [list, line, column, index]);
+            builder.as_item(item, &typ)
+        }
+        Builtin::Box(typ, expression) => {
+            let expression = cg_expression(builder, *expression);
+            builder.to_item(expression, &typ)
+        }
+        Builtin::Exit(status) => {
+            let status = cg_expression(builder, *status);
+            builder.call(nm!("exit_"), &mut [status]);
+            builder.unit()
+        }
+        Builtin::Convert(from, to, expression) => {
+            let expression = cg_expression(builder, *expression);
+            let typ = builder.llvm_type(&to);
+            match (from, to) {
+                (SwindleType::Float, SwindleType::Float) => expression,
+                (from, SwindleType::Float) if from.is_unsigned() => {
+                    LLVMBuildUIToFP(builder.builder, expression, typ, nm!("convert"))
+                }
+                (_, SwindleType::Float) => {
+                    LLVMBuildSIToFP(builder.builder, expression, typ, nm!("convert"))
+                }
+                // a u64 reaches past the largest int, so those floats saturate at its own maximum,
+                // and negative ones at 0
+                (SwindleType::Float, SwindleType::U64) => {
+                    let float = LLVMDoubleTypeInContext(builder.context);
+                    let expression = builder.clamp_float(expression, 0.0, f64::INFINITY);
+                    let int = builder.float_to_int(expression);
+                    let uint = LLVMBuildFPToUI(builder.builder, expression, typ, nm!("uint"));
+                    let limit = LLVMConstReal(float, 18_446_744_073_709_551_616.0);
+                    let too_big = LLVMBuildFCmp(
+                        builder.builder,
+                        LLVMRealOGE,
+                        expression,
+                        limit,
+                        nm!("too_big"),
+                    );
+                    let uint = LLVMBuildSelect(
+                        builder.builder,
+                        too_big,
+                        builder.const_int(u64::MAX),
+                        uint,
+                        nm!("uint"),
+                    );
+                    let limit = LLVMConstReal(float, 9_223_372_036_854_775_808.0);
+                    let big =
+                        LLVMBuildFCmp(builder.builder, LLVMRealOGE, expression, limit, nm!("big"));
+                    LLVMBuildSelect(builder.builder, big, uint, int, nm!("convert"))
+                }
+                // other floats saturate at the bounds of their sized integer,
+                // so they fit once they've become an int
+                (SwindleType::Float, to) => {
+                    let expression = match to {
+                        SwindleType::U8 => builder.clamp_float(expression, 0.0, 255.0),
+                        SwindleType::I32 => {
+                            builder.clamp_float(expression, -2_147_483_648.0, 2_147_483_647.0)
+                        }
+                        SwindleType::U32 => builder.clamp_float(expression, 0.0, 4_294_967_295.0),
+                        _ => expression,
+                    };
+                    let int = builder.float_to_int(expression);
+                    LLVMBuildIntCast2(builder.builder, int, typ, LLVM_TRUE, nm!("convert"))
+                }
+                // integers get truncated, or extended according to the sign of the original
+                (from, _) => {
+                    let signed = if from.is_unsigned() {
+                        LLVM_FALSE
+                    } else {
+                        LLVM_TRUE
+                    };
+                    LLVMBuildIntCast2(builder.builder, expression, typ, signed, nm!("convert"))
+                }
+            }
+        }
     }
 }
 
-unsafe fn cg_forexp(builder: &mut Builder, forexp: ForExp<PCG>) -> LLVMValueRef {
-    let old_break_bb = builder.break_bb;
-    let old_continue_bb = builder.break_bb;
+/*
+ * Calls an RTS function, passing first any destination for its result, then the extra arguments
+ * (like a line and column), then the arguments themselves
+ */
+unsafe fn cg_runtime(
+    builder: &mut Builder,
+    func: &str,
+    extra: &[LLVMValueRef],
+    args: Vec<Expression<PCG>>,
+    typ: SwindleType,
+) -> LLVMValueRef {
+    let func = CString::new(func).unwrap();
+    let mut c_args = Vec::new();
+    // functions returning an RC store their result in the first argument,
+    // which includes any tuple since the RTS boxes them
+    let rc = if typ.is_rc() || typ.is_unboxed() {
+        let rc = builder.alloca_rc(nm!("rc"));
+        c_args.push(rc);
+        Some(rc)
+    } else {
+        None
+    };
+    c_args.extend_from_slice(extra);
+    for arg in args {
+        c_args.push(cg_expression(builder, arg));
+    }
+    let result = builder.call(func.as_ptr(), &mut c_args);
+    match typ {
+        // functions returning unit are void in the RTS
+        SwindleType::Unit => builder.unit(),
+        _ => builder.unbox_item(rc.unwrap_or(result), &typ),
+    }
+}
 
+unsafe fn cg_forexp(builder: &mut Builder, forexp: ForExp<PCG>) -> LLVMValueRef {
     //setup blocks and variables
     let current_block = LLVMGetInsertBlock(builder.builder);
     let next_block = LLVMGetNextBasicBlock(current_block);
     let start = LLVMInsertBasicBlockInContext(builder.context, next_block, nm!("start"));
     let then = LLVMInsertBasicBlockInContext(builder.context, next_block, nm!("then"));
+    let update = LLVMInsertBasicBlockInContext(builder.context, next_block, nm!("update"));
     let otherwise = LLVMInsertBasicBlockInContext(builder.context, next_block, nm!("otherwise"));
 
-    builder.break_bb = otherwise;
-    builder.continue_bb = start;
-
     LLVMPositionBuilderAtEnd(builder.builder, current_block);
     // initialize list
-    let item_type = LLVMConstInt(
-        LLVMInt32TypeInContext(builder.context),
-        match forexp.tag {
-            SwindleType::Int => 0,     // SW_INT
-            SwindleType::Bool => 1,    // SW_BOOL
-            SwindleType::Unit => 2,    // SW_UNIT
-            SwindleType::String => 3,  // SW_STRING
-            SwindleType::List(_) => 4, // SW_LIST
-        },
-        LLVM_FALSE,
-    );
+    let item_type = builder.item_type(&forexp.tag);
     let rc = LLVMBuildAlloca(
         builder.builder,
         LLVMGetTypeByName(builder.module, nm!("struct.RC")),
@@ -642,35 +1273,207 @@ unsafe fn cg_forexp(builder: &mut Builder, forexp: ForExp<PCG>) -> LLVMValueRef
     );
     cg_statement(builder, *forexp.init);
     LLVMBuildBr(builder.builder, start);
+    // continue skips the rest of the body, but still runs the update
+    builder.loops.push(LoopBlocks {
+        label: forexp.label,
+        list: Some(rc),
+        iterable: None,
+        break_bb: otherwise,
+        continue_bb: update,
+    });
     LLVMPositionBuilderAtEnd(builder.builder, start);
 
     let cond = cg_expression(builder, *forexp.cond);
     LLVMBuildCondBr(builder.builder, cond, then, otherwise);
     LLVMPositionBuilderAtEnd(builder.builder, then);
-    LLVMBuildCall(
-        builder.builder,
-        LLVMGetNamedFunction(builder.module, nm!("push_")),
-        [rc, cg_body(builder, forexp.body)].as_mut_ptr(),
-        2,
-        nm!(""),
-    );
+    // a body which ends in a jump has no value, and the loop only collects its break values
+    let ends_in_jump = forexp.body.ends_in_jump();
+    let body = cg_body(builder, forexp.body);
+    if !ends_in_jump {
+        LLVMBuildCall(
+            builder.builder,
+            LLVMGetNamedFunction(builder.module, nm!("push_")),
+            [rc, builder.to_item(body, &forexp.tag)].as_mut_ptr(),
+            2,
+            nm!(""),
+        );
+    }
+    LLVMBuildBr(builder.builder, update);
+    LLVMPositionBuilderAtEnd(builder.builder, update);
     cg_expression(builder, *forexp.update);
     LLVMBuildBr(builder.builder, start);
 
     LLVMPositionBuilderAtEnd(builder.builder, otherwise);
-
-    builder.break_bb = old_break_bb;
-    builder.continue_bb = old_continue_bb;
+    builder.loops.pop();
     rc
 }
 
-unsafe fn cg_ifexp(builder: &mut Builder, ifexp: IfExp<PCG>) -> LLVMValueRef {
-    let typ = match ifexp.tag {
-        SwindleType::Int => builder.int64_ty(),
-        SwindleType::Bool => builder.int1_ty(),
-        SwindleType::Unit => builder.int1_ty(),
-        SwindleType::List(_) | SwindleType::String => builder.rc_ty(),
+unsafe fn cg_destructure(
+    builder: &mut Builder,
+    bindings: Vec<(SwindleType, usize)>,
+    tuple: LLVMValueRef,
+) {
+    let types = bindings.iter().map(|(typ, _)| typ.clone()).collect();
+    let unboxed = SwindleType::Tuple(types).is_unboxed();
+    if !unboxed {
+        // hold on to the tuple until every item has been read out of it
+        builder.call(nm!("alloc"), &mut [tuple]);
+    }
+    for (idx, (typ, id)) in bindings.into_iter().enumerate() {
+        let item = if unboxed {
+            LLVMBuildExtractValue(builder.builder, tuple, idx as u32, nm!("item"))
+        } else {
+            let item = builder.call(
+                nm!("index_tuple_"),
+                &mut [tuple, builder.const_int(idx as u64)],
+            );
+            builder.as_item(item, &typ)
+        };
+        cg_store_item(builder, id, &typ, item);
+    }
+    if !unboxed {
+        builder.call(nm!("drop"), &mut [tuple]);
+    }
+}
+
+/*
+ * Stores an item which was read out of a tuple or list into a variable
+ */
+unsafe fn cg_store_item(builder: &mut Builder, id: usize, typ: &SwindleType, item: LLVMValueRef) {
+    let item = if typ.is_rc() {
+        // copy the RC out of the tuple or list, which might go away before the variable does
+        let rc = builder.alloca_rc(nm!("rc"));
+        let value = LLVMBuildLoad(builder.builder, item, nm!("item"));
+        LLVMBuildStore(builder.builder, value, rc);
+        builder.call(nm!("drop2"), &mut [builder.variables[id]]);
+        builder.call(nm!("alloc"), &mut [rc])
+    } else {
+        item
     };
+    LLVMBuildStore(builder.builder, item, builder.variables[id]);
+}
+
+unsafe fn cg_forinexp(builder: &mut Builder, forin: ForInExp<PCG>) -> LLVMValueRef {
+    let current_block = LLVMGetInsertBlock(builder.builder);
+    let next_block = LLVMGetNextBasicBlock(current_block);
+    let start = LLVMInsertBasicBlockInContext(builder.context, next_block, nm!("start"));
+    let then = LLVMInsertBasicBlockInContext(builder.context, next_block, nm!("then"));
+    let next = LLVMInsertBasicBlockInContext(builder.context, next_block, nm!("next"));
+    let otherwise = LLVMInsertBasicBlockInContext(builder.context, next_block, nm!("otherwise"));
+
+    LLVMPositionBuilderAtEnd(builder.builder, current_block);
+    let rc = if forin.collect {
+        let rc = builder.alloca_rc(nm!("for_list"));
+        builder.call(
+            nm!("rc_list"),
+            &mut [rc, builder.item_type(&forin.tag), builder.const_int(0)],
+        );
+        Some(rc)
+    } else {
+        None
+    };
+
+    // continue goes to next, which bumps the index
+    let index = LLVMBuildAlloca(builder.builder, builder.int64_ty(), nm!("index"));
+    let (iterable_type, iterable) = forin.iterable;
+    let iterable = cg_expression(builder, *iterable);
+    let (list, item_type, stop, step) = match iterable_type {
+        SwindleType::List(typ) => {
+            builder.call(nm!("alloc"), &mut [iterable]);
+            LLVMBuildStore(builder.builder, builder.const_int(0), index);
+            (Some(iterable), *typ, None, builder.const_int(1))
+        }
+        _ => {
+            let start = LLVMBuildExtractValue(builder.builder, iterable, 0, nm!("start"));
+            let stop = LLVMBuildExtractValue(builder.builder, iterable, 1, nm!("stop"));
+            let step = LLVMBuildExtractValue(builder.builder, iterable, 2, nm!("step"));
+            let inclusive = LLVMBuildExtractValue(builder.builder, iterable, 3, nm!("inclusive"));
+            LLVMBuildStore(builder.builder, start, index);
+            (None, SwindleType::Int, Some((stop, inclusive)), step)
+        }
+    };
+    LLVMBuildBr(builder.builder, start);
+    builder.loops.push(LoopBlocks {
+        label: forin.label,
+        list: rc,
+        iterable: list,
+        break_bb: otherwise,
+        continue_bb: next,
+    });
+
+    LLVMPositionBuilderAtEnd(builder.builder, start);
+    let idx = LLVMBuildLoad(builder.builder, index, nm!("index"));
+    let cond = match (list, stop) {
+        (Some(list), _) => {
+            let length = builder.call(nm!("length_list"), &mut [list]);
+            LLVMBuildICmp(builder.builder, LLVMIntSLT, idx, length, nm!("cond"))
+        }
+        (_, Some((stop, inclusive))) => {
+            // a range with a step of 0 is empty
+            let zero = builder.const_int(0);
+            let up = LLVMBuildICmp(builder.builder, LLVMIntSGT, step, zero, nm!("up"));
+            let down = LLVMBuildICmp(builder.builder, LLVMIntSLT, step, zero, nm!("down"));
+            let below = LLVMBuildICmp(builder.builder, LLVMIntSLT, idx, stop, nm!("below"));
+            let above = LLVMBuildICmp(builder.builder, LLVMIntSGT, idx, stop, nm!("above"));
+            let at_stop = LLVMBuildICmp(builder.builder, LLVMIntEQ, idx, stop, nm!("at_stop"));
+            let at_stop = LLVMBuildAnd(builder.builder, inclusive, at_stop, nm!("at_stop"));
+            let below = LLVMBuildOr(builder.builder, below, at_stop, nm!("below"));
+            let above = LLVMBuildOr(builder.builder, above, at_stop, nm!("above"));
+            let up = LLVMBuildAnd(builder.builder, up, below, nm!("up"));
+            let down = LLVMBuildAnd(builder.builder, down, above, nm!("down"));
+            LLVMBuildOr(builder.builder, up, down, nm!("cond"))
+        }
+        _ => unreachable!(),
+    };
+    LLVMBuildCondBr(builder.builder, cond, then, otherwise);
+
+    LLVMPositionBuilderAtEnd(builder.builder, then);
+    let item = match list {
+        Some(list) => {
+            let item = builder.call(nm!("index_list_"), &mut [list, idx]);
+            builder.as_item(item, &item_type)
+        }
+        None => idx,
+    };
+    if forin.vars.len() == 1 {
+        let (typ, id) = &forin.vars[0];
+        cg_store_item(builder, *id, typ, item);
+    } else {
+        cg_destructure(builder, forin.vars, item);
+    }
+    let ends_in_jump = forin.body.ends_in_jump();
+    let body = cg_body(builder, forin.body);
+    match rc {
+        Some(rc) if !ends_in_jump => {
+            builder.call(nm!("push_"), &mut [rc, builder.to_item(body, &forin.tag)]);
+        }
+        _ => (),
+    }
+    LLVMBuildBr(builder.builder, next);
+
+    LLVMPositionBuilderAtEnd(builder.builder, next);
+    let idx = LLVMBuildLoad(builder.builder, index, nm!("index"));
+    let bumped = LLVMBuildAdd(builder.builder, idx, step, nm!("index"));
+    LLVMBuildStore(builder.builder, bumped, index);
+    // the loop ends if the index overflows, which can happen stepping past high in low..=high,
+    // that is if it went down with a positive step or up with a negative one (a step of 0 never
+    // gets here)
+    let zero = builder.const_int(0);
+    let up = LLVMBuildICmp(builder.builder, LLVMIntSGT, step, zero, nm!("up"));
+    let wrapped = LLVMBuildICmp(builder.builder, LLVMIntSLT, bumped, idx, nm!("wrapped"));
+    let overflow = LLVMBuildICmp(builder.builder, LLVMIntEQ, up, wrapped, nm!("overflow"));
+    LLVMBuildCondBr(builder.builder, overflow, otherwise, start);
+
+    LLVMPositionBuilderAtEnd(builder.builder, otherwise);
+    if let Some(list) = list {
+        builder.call(nm!("drop"), &mut [list]);
+    }
+    builder.loops.pop();
+    rc.unwrap_or_else(|| builder.unit())
+}
+
+unsafe fn cg_ifexp(builder: &mut Builder, ifexp: IfExp<PCG>) -> LLVMValueRef {
+    let typ = builder.llvm_type(&ifexp.tag);
     let current_block = LLVMGetInsertBlock(builder.builder);
     let next_block = LLVMGetNextBasicBlock(current_block);
     let if_result = LLVMBuildAlloca(builder.builder, typ, nm!("if_result"));
@@ -12,15 +12,18 @@ extern crate lalrpop_util;
 lalrpop_mod!(#[allow(clippy::all)] pub parser);
 
 fn main() {
+    let mut args = env::args().collect::<Vec<_>>();
+    // `swindle test file.sw` compiles the test blocks of file.sw into a test harness
+    let testing = args.len() > 2 && args[1] == "test";
     let code = {
-        let file_name = env::args().collect::<Vec<_>>().pop().unwrap();
+        let file_name = args.pop().unwrap();
         let mut file = File::open(&file_name).unwrap();
         let mut code = String::new();
         file.read_to_string(&mut code).unwrap();
         code
     };
 
-    let parsed = parser::ProgramParser::new().parse(&code);
+    let parsed = parser::ProgramParser::new().parse(&code, 0, &code);
     let result = match parsed {
         Ok(p) => type_program(p),
         Err(err) => {
@@ -31,8 +34,8 @@ fn main() {
 
     match result {
         Ok(program) => {
-            let (program, variables, strings) = preprocess_program(program);
-            cg_program(program, variables, strings);
+            let (program, variables, strings) = preprocess_program(program, testing);
+            cg_program(program, variables, strings, testing);
         }
         Err(e) => println!("{}", e),
     }
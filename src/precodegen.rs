@@ -55,17 +55,21 @@ impl PCGState {
     }
 }
 
+/*
+ * Test blocks only get compiled when testing, and nothing else does
+ */
 pub fn preprocess_program(
     program: Program<Typed>,
+    testing: bool,
 ) -> (Program<PCG>, Vec<SwindleType>, Vec<String>) {
     let mut state = PCGState::new();
     let mut statements = Vec::new();
     for tagged_stmt in program.statements {
+        if testing != matches!(tagged_stmt.statement, Statement::Test(_, _)) {
+            continue;
+        }
         statements.push(TaggedStatement {
-            tag: match tagged_stmt.tag {
-                SwindleType::List(_) | SwindleType::String => true,
-                _ => false,
-            },
+            tag: tagged_stmt.tag.is_rc(),
             statement: preprocess_statement(&mut state, tagged_stmt.statement),
         })
     }
@@ -79,11 +83,23 @@ fn preprocess_statement(state: &mut PCGState, statement: Statement<Typed>) -> St
             state.add_variable(varname, typ),
             preprocess_expression(state, *expression),
         ),
-        Statement::Break => Statement::Break,
-        Statement::Continue => Statement::Continue,
+        Statement::Destructure(bindings, expression) => {
+            let expression = preprocess_expression(state, *expression);
+            let bindings = bindings
+                .into_iter()
+                .map(|(typ, varname)| (typ.clone(), state.add_variable(varname, typ)))
+                .collect();
+            Statement::Destructure(bindings, expression)
+        }
+        Statement::Break(label, value) => Statement::Break(
+            label,
+            value.map(|(typ, value)| (typ, preprocess_expression(state, *value))),
+        ),
+        Statement::Continue(label) => Statement::Continue(label),
         Statement::Expression(expression) => {
             Statement::Expression(preprocess_expression(state, *expression))
         }
+        Statement::Test(name, body) => Statement::Test(name, preprocess_body(state, body)),
     }
 }
 
@@ -97,6 +113,12 @@ fn preprocess_expression(
             preprocess_lvalue(state, *lvalue),
             preprocess_expression(state, *expression),
         ),
+        Expression::Range(low, high, step, inclusive) => Expression::Range(
+            preprocess_orexp(state, *low),
+            preprocess_orexp(state, *high),
+            step.map(|step| preprocess_orexp(state, *step)),
+            inclusive,
+        ),
         Expression::OrExp(orexp) => Expression::OrExp(preprocess_orexp(state, *orexp)),
     })
 }
@@ -104,7 +126,9 @@ fn preprocess_expression(
 fn preprocess_lvalue(state: &mut PCGState, lvalue: LValue<Typed>) -> Box<LValue<PCG>> {
     Box::new(match lvalue {
         LValue::Variable(varname) => LValue::Variable(state.get_variable(varname)),
-        LValue::Index(lvalue, index) => LValue::Index(
+        LValue::Index(file_posn, typ, lvalue, index) => LValue::Index(
+            file_posn,
+            typ,
             preprocess_lvalue(state, *lvalue),
             preprocess_expression(state, *index),
         ),
@@ -135,8 +159,8 @@ fn preprocess_compexp(state: &mut PCGState, compexp: CompExp<Typed>) -> Box<Comp
     Box::new(match compexp {
         CompExp::Comp(op, addexp1, addexp2) => {
             let op = match op {
-                CompOp::Leq => CompOp::Leq,
-                CompOp::Lt => CompOp::Lt,
+                CompOp::Leq(t) => CompOp::Leq(t),
+                CompOp::Lt(t) => CompOp::Lt(t),
                 CompOp::Eq(t) => CompOp::Eq(t),
             };
             CompExp::Comp(
@@ -151,15 +175,17 @@ fn preprocess_compexp(state: &mut PCGState, compexp: CompExp<Typed>) -> Box<Comp
 
 fn preprocess_addexp(state: &mut PCGState, addexp: AddExp<Typed>) -> Box<AddExp<PCG>> {
     Box::new(match addexp {
-        AddExp::Add(op, mulexp, addexp) => {
+        AddExp::Add(op, addexp, mulexp) => {
             let op = match op {
                 AddOp::Sum(t) => AddOp::Sum(t),
-                AddOp::Difference => AddOp::Difference,
+                AddOp::Difference(t) => AddOp::Difference(t),
+                AddOp::Union(t) => AddOp::Union(t),
+                AddOp::Xor(t) => AddOp::Xor(t),
             };
             AddExp::Add(
                 op,
-                preprocess_mulexp(state, *mulexp),
                 preprocess_addexp(state, *addexp),
+                preprocess_mulexp(state, *mulexp),
             )
         }
         AddExp::MulExp(mulexp) => AddExp::MulExp(preprocess_mulexp(state, *mulexp)),
@@ -168,18 +194,28 @@ fn preprocess_addexp(state: &mut PCGState, addexp: AddExp<Typed>) -> Box<AddExp<
 
 fn preprocess_mulexp(state: &mut PCGState, mulexp: MulExp<Typed>) -> Box<MulExp<PCG>> {
     Box::new(match mulexp {
-        MulExp::Mul(op, unary, mulexp) => MulExp::Mul(
-            op,
-            preprocess_unary(state, *unary),
-            preprocess_mulexp(state, *mulexp),
-        ),
+        MulExp::Mul(op, mulexp, unary) => {
+            let op = match op {
+                MulOp::Product(t) => MulOp::Product(t),
+                MulOp::Quotient(t) => MulOp::Quotient(t),
+                MulOp::Remainder(t) => MulOp::Remainder(t),
+                MulOp::Intersection(t) => MulOp::Intersection(t),
+                MulOp::ShiftLeft(t) => MulOp::ShiftLeft(t),
+                MulOp::ShiftRight(t) => MulOp::ShiftRight(t),
+            };
+            MulExp::Mul(
+                op,
+                preprocess_mulexp(state, *mulexp),
+                preprocess_unary(state, *unary),
+            )
+        }
         MulExp::Unary(unary) => MulExp::Unary(preprocess_unary(state, *unary)),
     })
 }
 
 fn preprocess_unary(state: &mut PCGState, unary: Unary<Typed>) -> Box<Unary<PCG>> {
     Box::new(match unary {
-        Unary::Negate(unary) => Unary::Negate(preprocess_unary(state, *unary)),
+        Unary::Negate(typ, unary) => Unary::Negate(typ, preprocess_unary(state, *unary)),
         Unary::Not(unary) => Unary::Not(preprocess_unary(state, *unary)),
         Unary::Primary(primary) => Unary::Primary(Box::new(preprocess_primary(state, *primary))),
     })
@@ -189,17 +225,29 @@ fn preprocess_primary(state: &mut PCGState, primary: Primary<Typed>) -> Primary<
     match primary {
         Primary::Paren(e) => Primary::Paren(preprocess_expression(state, *e)),
         Primary::IntLit(n) => Primary::IntLit(n),
+        Primary::FloatLit(f) => Primary::FloatLit(f),
         Primary::StringLit(s) => Primary::StringLit(state.add_string(s)),
         Primary::BoolLit(b) => Primary::BoolLit(b),
+        Primary::CharLit(c) => Primary::CharLit(c),
         Primary::Variable(v) => Primary::Variable(state.get_variable(v)),
         Primary::IfExp(ifexp) => Primary::IfExp(preprocess_ifexp(state, ifexp)),
         Primary::ForExp(forexp) => Primary::ForExp(preprocess_forexp(state, forexp)),
+        Primary::ForInExp(forin) => Primary::ForInExp(preprocess_forinexp(state, forin)),
+        Primary::Comprehension(clauses, forin) => {
+            Primary::Comprehension(clauses, preprocess_forinexp(state, forin))
+        }
         Primary::StatementExp(body) => Primary::StatementExp(preprocess_body(state, body)),
         Primary::Index(typ, list, index) => Primary::Index(
             typ,
             Box::new(preprocess_primary(state, *list)),
             preprocess_expression(state, *index),
         ),
+        Primary::Slice(typ, list, low, high) => Primary::Slice(
+            typ,
+            Box::new(preprocess_primary(state, *list)),
+            low.map(|low| preprocess_expression(state, *low)),
+            high.map(|high| preprocess_expression(state, *high)),
+        ),
         Primary::Builtin(builtin) => Primary::Builtin(preprocess_builtin(state, builtin)),
         Primary::List(typ, items) => {
             let mut new_items = Vec::new();
@@ -208,6 +256,30 @@ fn preprocess_primary(state: &mut PCGState, primary: Primary<Typed>) -> Primary<
             }
             Primary::List(typ, new_items)
         }
+        Primary::Map(key, value, entries) => {
+            let mut new_entries = Vec::new();
+            for (k, v) in entries {
+                new_entries.push((
+                    *preprocess_expression(state, k),
+                    *preprocess_expression(state, v),
+                ));
+            }
+            Primary::Map(key, value, new_entries)
+        }
+        Primary::Set(typ, items) => {
+            let mut new_items = Vec::new();
+            for item in items {
+                new_items.push(*preprocess_expression(state, item));
+            }
+            Primary::Set(typ, new_items)
+        }
+        Primary::Tuple(types, items) => {
+            let mut new_items = Vec::new();
+            for item in items {
+                new_items.push(*preprocess_expression(state, item));
+            }
+            Primary::Tuple(types, new_items)
+        }
         Primary::Function(_) => unimplemented!(),
         Primary::FunCall(_, _) => unimplemented!(),
         Primary::Unit => Primary::Unit,
@@ -224,6 +296,37 @@ fn preprocess_builtin(state: &mut PCGState, builtin: Builtin<Typed>) -> Builtin<
             }
             Builtin::Write(newline, new_args)
         }
+        Builtin::Assert(file_posn, e, message) => Builtin::Assert(
+            file_posn,
+            preprocess_expression(state, *e),
+            message.map(|message| preprocess_expression(state, *message)),
+        ),
+        Builtin::Panic(file_posn, e) => Builtin::Panic(file_posn, preprocess_expression(state, *e)),
+        Builtin::Exit(e) => Builtin::Exit(preprocess_expression(state, *e)),
+        Builtin::Convert(from, to, e) => {
+            Builtin::Convert(from, to, preprocess_expression(state, *e))
+        }
+        Builtin::Box(typ, e) => Builtin::Box(typ, preprocess_expression(state, *e)),
+        Builtin::Runtime(func, args, typ) => {
+            let mut new_args = Vec::new();
+            for arg in args {
+                new_args.push(*preprocess_expression(state, arg));
+            }
+            Builtin::Runtime(func, new_args, typ)
+        }
+        Builtin::Checked(file_posn, func, args, typ) => {
+            let mut new_args = Vec::new();
+            for arg in args {
+                new_args.push(*preprocess_expression(state, arg));
+            }
+            Builtin::Checked(file_posn, func, new_args, typ)
+        }
+        Builtin::Index(file_posn, typ, list, index) => Builtin::Index(
+            file_posn,
+            typ,
+            Box::new(preprocess_primary(state, *list)),
+            preprocess_expression(state, *index),
+        ),
     }
 }
 
@@ -235,6 +338,7 @@ fn preprocess_forexp(state: &mut PCGState, forexp: ForExp<Typed>) -> ForExp<PCG>
     let body = preprocess_body(state, forexp.body);
     ForExp {
         tag,
+        label: forexp.label,
         init,
         cond,
         update,
@@ -242,6 +346,26 @@ fn preprocess_forexp(state: &mut PCGState, forexp: ForExp<Typed>) -> ForExp<PCG>
     }
 }
 
+fn preprocess_forinexp(state: &mut PCGState, forin: ForInExp<Typed>) -> ForInExp<PCG> {
+    let tag = forin.tag;
+    let (iterable_type, iterable) = forin.iterable;
+    let iterable = (iterable_type, preprocess_expression(state, *iterable));
+    let vars = forin
+        .vars
+        .into_iter()
+        .map(|(typ, varname)| (typ.clone(), state.add_variable(varname, typ)))
+        .collect();
+    let body = preprocess_body(state, forin.body);
+    ForInExp {
+        tag,
+        label: forin.label,
+        vars,
+        iterable,
+        body,
+        collect: forin.collect,
+    }
+}
+
 fn preprocess_ifexp(state: &mut PCGState, ifexp: IfExp<Typed>) -> IfExp<PCG> {
     let tag = ifexp.tag;
     let cond = preprocess_expression(state, *ifexp.cond);
@@ -271,13 +395,14 @@ fn preprocess_body(state: &mut PCGState, body: Body<Typed>) -> Body<PCG> {
     let mut statements = Vec::new();
     for tagged_stmt in body.statements {
         statements.push(TaggedStatement::new(
-            tagged_stmt.tag == SwindleType::String,
+            tagged_stmt.tag.is_rc(),
             preprocess_statement(state, tagged_stmt.statement),
         ));
     }
 
+    // the last statement is the value of the body, so it's up to whoever uses it
     if let Some(tagged_stmt) = statements.last_mut() {
-        tagged_stmt.tag &= true;
+        tagged_stmt.tag = false;
     }
 
     Body { statements }
@@ -1,7 +1,10 @@
 use crate::ast::*;
 use crate::error::*;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::default::Default;
+use std::fmt;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub struct Typed {}
@@ -22,6 +25,25 @@ where
 {
     Length(T::TypeTag, Box<Expression<T>>),
     Write(bool, Vec<(Expression<T>, T::TypeTag)>),
+    Assert(FilePosition, Box<Expression<T>>, Option<Box<Expression<T>>>),
+    Panic(FilePosition, Box<Expression<T>>),
+    Exit(Box<Expression<T>>),
+    // @to_int, @to_float, @to_u8 and so on, tagged with the types being converted from and to
+    Convert(T::TypeTag, T::TypeTag, Box<Expression<T>>),
+    // an unboxed tuple, which has to be boxed before it can be passed to the RTS,
+    // or a narrow integer, which has to be widened
+    Box(T::TypeTag, Box<Expression<T>>),
+    // a builtin which is just a call to the named RTS function, with the given result type
+    Runtime(&'static str, Vec<Expression<T>>, T::TypeTag),
+    // like Runtime, but the RTS function can panic, so it also gets the line and column
+    Checked(FilePosition, &'static str, Vec<Expression<T>>, T::TypeTag),
+    // indexing a list or map, tagged with its type, which panics if the index or key is missing
+    Index(
+        FilePosition,
+        T::TypeTag,
+        Box<Primary<T>>,
+        Box<Expression<T>>,
+    ),
 }
 
 // copy and clone might not work in the future
@@ -31,7 +53,103 @@ pub enum SwindleType {
     String,
     Bool,
     Unit,
+    Char,
     List(Box<SwindleType>),
+    Map(Box<SwindleType>, Box<SwindleType>),
+    Set(Box<SwindleType>),
+    Tuple(Vec<SwindleType>),
+    Range,
+    Float,
+    U8,
+    I32,
+    U32,
+    U64,
+}
+
+impl fmt::Display for SwindleType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SwindleType::Int => write!(f, "int"),
+            SwindleType::String => write!(f, "string"),
+            SwindleType::Bool => write!(f, "bool"),
+            SwindleType::Unit => write!(f, "unit"),
+            SwindleType::Char => write!(f, "char"),
+            SwindleType::List(typ) => write!(f, "[{}]", typ),
+            SwindleType::Map(key, value) => write!(f, "{{{}: {}}}", key, value),
+            SwindleType::Set(typ) => write!(f, "{{{}}}", typ),
+            SwindleType::Tuple(types) => {
+                let types = types.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+                write!(f, "({})", types.join(", "))
+            }
+            SwindleType::Range => write!(f, "range"),
+            SwindleType::Float => write!(f, "float"),
+            SwindleType::U8 => write!(f, "u8"),
+            SwindleType::I32 => write!(f, "i32"),
+            SwindleType::U32 => write!(f, "u32"),
+            SwindleType::U64 => write!(f, "u64"),
+        }
+    }
+}
+
+impl SwindleType {
+    /*
+     * Whether values of this type are reference counted
+     */
+    pub fn is_rc(&self) -> bool {
+        match self {
+            SwindleType::String
+            | SwindleType::List(_)
+            | SwindleType::Map(_, _)
+            | SwindleType::Set(_) => true,
+            SwindleType::Tuple(types) => types.iter().any(SwindleType::is_rc),
+            _ => false,
+        }
+    }
+
+    /*
+     * Ranges, and tuples which don't hold anything reference counted, are stored unboxed
+     */
+    pub fn is_unboxed(&self) -> bool {
+        match self {
+            SwindleType::Tuple(_) => !self.is_rc(),
+            SwindleType::Range => true,
+            _ => false,
+        }
+    }
+
+    /*
+     * Integers smaller than an int, which the RTS takes and returns widened to 64 bits
+     */
+    pub fn is_narrow(&self) -> bool {
+        matches!(self, SwindleType::U8 | SwindleType::I32 | SwindleType::U32)
+    }
+
+    pub fn is_integer(&self) -> bool {
+        match self {
+            SwindleType::Int | SwindleType::U64 => true,
+            _ => self.is_narrow(),
+        }
+    }
+
+    pub fn is_unsigned(&self) -> bool {
+        matches!(self, SwindleType::U8 | SwindleType::U32 | SwindleType::U64)
+    }
+
+    pub fn is_number(&self) -> bool {
+        self.is_integer() || *self == SwindleType::Float
+    }
+
+    /*
+     * Whether values of this type can be compared and hashed, which maps and sets can't be
+     */
+    pub fn is_comparable(&self) -> bool {
+        match self {
+            SwindleType::List(typ) => typ.is_comparable(),
+            SwindleType::Tuple(types) => types.iter().all(SwindleType::is_comparable),
+            SwindleType::Map(_, _) | SwindleType::Set(_) => false,
+            _ => true,
+        }
+    }
 }
 
 type TyperResult<A> = Result<A, SwindleError>;
@@ -40,7 +158,12 @@ type TyperResult<A> = Result<A, SwindleError>;
 struct TyperState {
     types: HashMap<String, SwindleType>,
     file_posn: FilePosition,
-    in_loop: bool,
+    // the labels of the loops we're in, innermost last
+    loops: Vec<Option<String>>,
+    // which loop each break value goes to, shared between clones since bodies get their own state
+    breaks: Rc<RefCell<Vec<(usize, SwindleType, FilePosition)>>>,
+    // how many variables the typechecker has declared itself, also shared between clones
+    fresh: Rc<Cell<usize>>,
 }
 
 impl TyperState {
@@ -48,7 +171,29 @@ impl TyperState {
         TyperState {
             types: HashMap::new(),
             file_posn: Default::default(),
-            in_loop: false,
+            loops: Vec::new(),
+            breaks: Rc::new(RefCell::new(Vec::new())),
+            fresh: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /*
+     * A name for a variable the typechecker declares itself, which can't clash with any other
+     * since it starts with an @
+     */
+    fn fresh_name(&self, prefix: &str) -> String {
+        let n = self.fresh.get();
+        self.fresh.set(n + 1);
+        format!("{}{}", prefix, n)
+    }
+
+    /*
+     * Finds which loop a break or continue refers to
+     */
+    fn find_loop(&self, label: &Option<String>) -> Option<usize> {
+        match label {
+            Some(_) => self.loops.iter().rposition(|l| l == label),
+            None => self.loops.len().checked_sub(1),
         }
     }
 
@@ -74,7 +219,11 @@ pub fn type_program(program: Program<Parsed>) -> TyperResult<Program<Typed>> {
     let mut statements = Vec::new();
     for tagged_stmt in program.statements {
         state.file_posn = tagged_stmt.tag;
-        match type_statement(&mut state, tagged_stmt.statement) {
+        let result = match tagged_stmt.statement {
+            Statement::Test(name, body) => type_test(state.file_posn, name, body),
+            statement => type_statement(&mut state, statement),
+        };
+        match result {
             Ok((stmt, t)) => statements.push(TaggedStatement::new(t, stmt)),
             Err(e) => return Err(e),
         }
@@ -83,6 +232,20 @@ pub fn type_program(program: Program<Parsed>) -> TyperResult<Program<Typed>> {
     Ok(Program { statements })
 }
 
+/*
+ * Tests are typechecked in their own scope, so that they can't see (or depend on)
+ * anything declared at the top level
+ */
+fn type_test(
+    file_posn: FilePosition,
+    name: String,
+    body: Body<Parsed>,
+) -> TyperResult<(Statement<Typed>, SwindleType)> {
+    let mut state = TyperState::new();
+    state.file_posn = file_posn;
+    type_body(&mut state, body).map(|(body, _)| (Statement::Test(name, body), SwindleType::Unit))
+}
+
 fn type_statement(
     state: &mut TyperState,
     statement: Statement<Parsed>,
@@ -106,39 +269,100 @@ fn type_statement(
                 })
             }
         }
-        Statement::Break => {
-            if state.in_loop {
-                Ok((Statement::Break, SwindleType::Unit))
-            } else {
-                throw_error(
-                    "can only use a break statement in a loop".to_string(),
-                    state.file_posn,
-                )
+        Statement::Destructure(bindings, expression) => {
+            let bindings = bindings
+                .into_iter()
+                .map(|(typ, varname)| (type_to_swindle_type(typ), varname))
+                .collect::<Vec<_>>();
+            for (idx, (_, varname)) in bindings.iter().enumerate() {
+                if state.get(varname).is_some() || bindings[..idx].iter().any(|(_, v)| v == varname)
+                {
+                    return throw_error(
+                        "cannot declare a variable twice".to_string(),
+                        state.file_posn,
+                    );
+                }
             }
+
+            type_expression(state, *expression).and_then(|(e, t)| {
+                let types = bindings.iter().map(|(typ, _)| typ.clone()).collect();
+                if t == SwindleType::Tuple(types) {
+                    for (typ, varname) in &bindings {
+                        state.insert(varname.to_string(), typ.clone());
+                    }
+                    Ok((Statement::Destructure(bindings, e), SwindleType::Unit))
+                } else {
+                    throw_error("bad types for destructure".to_string(), state.file_posn)
+                }
+            })
         }
-        Statement::Continue => {
-            if state.in_loop {
-                Ok((Statement::Continue, SwindleType::Unit))
-            } else {
-                throw_error(
-                    "can only use a continue statement in a loop".to_string(),
-                    state.file_posn,
-                )
+        Statement::Break(label, value) => match (state.find_loop(&label), &label) {
+            (Some(depth), _) => {
+                let value = match value {
+                    Some(((), value)) => match type_expression(state, *value) {
+                        Ok((value, typ)) => {
+                            let posn = state.file_posn;
+                            state.breaks.borrow_mut().push((depth, typ.clone(), posn));
+                            Some((typ, value))
+                        }
+                        Err(e) => return Err(e),
+                    },
+                    None => None,
+                };
+                Ok((Statement::Break(label, value), SwindleType::Unit))
             }
-        }
+            (None, Some(label)) => throw_error(
+                format!("undeclared loop label: '{}", label),
+                state.file_posn,
+            ),
+            (None, None) => throw_error(
+                "can only use a break statement in a loop".to_string(),
+                state.file_posn,
+            ),
+        },
+        Statement::Continue(label) => match (state.find_loop(&label), &label) {
+            (Some(_), _) => Ok((Statement::Continue(label), SwindleType::Unit)),
+            (None, Some(label)) => throw_error(
+                format!("undeclared loop label: '{}", label),
+                state.file_posn,
+            ),
+            (None, None) => throw_error(
+                "can only use a continue statement in a loop".to_string(),
+                state.file_posn,
+            ),
+        },
         Statement::Expression(expression) => {
             type_expression(state, *expression).map(|(e, t)| (Statement::Expression(e), t))
         }
+        Statement::Test(_, _) => throw_error(
+            "tests can only be declared at the top level".to_string(),
+            state.file_posn,
+        ),
     }
 }
 
 fn type_to_swindle_type(typ: Type) -> SwindleType {
     match typ {
         Type::Int => SwindleType::Int,
+        Type::Float => SwindleType::Float,
+        Type::U8 => SwindleType::U8,
+        Type::I32 => SwindleType::I32,
+        Type::U32 => SwindleType::U32,
+        Type::U64 => SwindleType::U64,
         Type::String => SwindleType::String,
         Type::Bool => SwindleType::Bool,
         Type::Unit => SwindleType::Unit,
+        Type::Char => SwindleType::Char,
         Type::List(typ) => SwindleType::List(Box::new(type_to_swindle_type(*typ))),
+        Type::Map(key, value) => SwindleType::Map(
+            Box::new(type_to_swindle_type(*key)),
+            Box::new(type_to_swindle_type(*value)),
+        ),
+        Type::Set(typ) => SwindleType::Set(Box::new(type_to_swindle_type(*typ))),
+        Type::Tuple(types) => {
+            SwindleType::Tuple(types.into_iter().map(type_to_swindle_type).collect())
+        }
+        Type::Range => SwindleType::Range,
         Type::Fn(_, _) => unimplemented!("{:?}", typ),
     }
 }
@@ -159,6 +383,29 @@ fn type_expression(
                 })
             })
         }
+        Expression::Range(low, high, step, inclusive) => {
+            let mut bounds = Vec::new();
+            for bound in [Some(low), Some(high), step] {
+                bounds.push(match bound.map(|bound| type_orexp(state, *bound)) {
+                    Some(Ok((bound, SwindleType::Int))) => Some(bound),
+                    Some(Err(e)) => return Err(e),
+                    Some(_) => {
+                        return throw_error(
+                            "range bounds and steps must be integers".to_string(),
+                            state.file_posn,
+                        )
+                    }
+                    None => None,
+                });
+            }
+            let step = bounds.pop().unwrap();
+            let high = bounds.pop().unwrap().unwrap();
+            let low = bounds.pop().unwrap().unwrap();
+            Ok((
+                Box::new(Expression::Range(low, high, step, inclusive)),
+                SwindleType::Range,
+            ))
+        }
         Expression::OrExp(orexp) => {
             type_orexp(state, *orexp).map(|(o, t)| (Box::new(Expression::OrExp(o)), t))
         }
@@ -174,14 +421,21 @@ fn type_lvalue(
             Some(typ) => Ok((Box::new(LValue::Variable(varname)), typ)),
             None => throw_error(format!("undeclared variable {}", varname), state.file_posn),
         },
-        LValue::Index(lvalue, index) => type_lvalue(state, *lvalue).and_then(|(lv, tlv)| {
-            type_expression(state, *index).and_then(|(index, tindex)| match (tlv, tindex) {
-                (SwindleType::List(typ), SwindleType::Int) => {
-                    Ok((Box::new(LValue::Index(lv, index)), *typ))
-                }
-                _ => throw_error("you absolute buffoon".to_string(), state.file_posn),
+        LValue::Index(posn, (), lvalue, index) => {
+            type_lvalue(state, *lvalue).and_then(|(lv, tlv)| {
+                type_expression(state, *index).and_then(|(index, tindex)| match (&tlv, tindex) {
+                    (SwindleType::List(typ), SwindleType::Int) => {
+                        let typ = (**typ).clone();
+                        Ok((Box::new(LValue::Index(posn, tlv, lv, index)), typ))
+                    }
+                    (SwindleType::Map(key, value), tindex) if **key == tindex => {
+                        let typ = (**value).clone();
+                        Ok((Box::new(LValue::Index(posn, tlv, lv, index)), typ))
+                    }
+                    _ => throw_error("you absolute buffoon".to_string(), state.file_posn),
+                })
             })
-        }),
+        }
     }
 }
 
@@ -205,14 +459,12 @@ fn type_forexp(
         Err(e) => return Err(e),
     };
 
-    let (body, body_ty) = match type_body(state, forexp.body) {
-        Ok(res) => res,
-        Err(e) => return Err(e),
-    };
+    let (body, body_ty) = type_loop_body(state, forexp.label.clone(), forexp.body)?;
 
     Ok((
         ForExp {
             tag: body_ty.clone(),
+            label: forexp.label,
             init,
             cond,
             update,
@@ -222,6 +474,120 @@ fn type_forexp(
     ))
 }
 
+/*
+ * The loop variables only exist inside the body, which collects a list like type_forexp
+ */
+fn type_forinexp(
+    state: &mut TyperState,
+    forin: ForInExp<Parsed>,
+) -> TyperResult<(ForInExp<Typed>, SwindleType)> {
+    let ((), iterable) = forin.iterable;
+    let (iterable, item_type) = type_iterable(state, *iterable)?;
+
+    let types = match (forin.vars.len(), item_type) {
+        (1, typ) => vec![typ],
+        (n, SwindleType::Tuple(types)) if types.len() == n => types,
+        _ => return throw_error("bad types for destructure".to_string(), state.file_posn),
+    };
+
+    let mut body_state = state.clone();
+    let mut vars = Vec::new();
+    for (((), varname), typ) in forin.vars.into_iter().zip(types) {
+        vars.push((typ, varname));
+    }
+    declare_loop_vars(&mut body_state, &vars)?;
+
+    let (body, body_ty) = type_loop_body(&mut body_state, forin.label.clone(), forin.body)?;
+
+    Ok((
+        ForInExp {
+            tag: body_ty.clone(),
+            label: forin.label,
+            vars,
+            iterable,
+            body,
+            collect: forin.collect,
+        },
+        SwindleType::List(Box::new(body_ty)),
+    ))
+}
+
+// what a for loop goes over, along with its type
+type Iterable = (SwindleType, Box<Expression<Typed>>);
+
+/*
+ * Strings are iterated over by their chars
+ */
+fn type_iterable(
+    state: &mut TyperState,
+    iterable: Expression<Parsed>,
+) -> TyperResult<(Iterable, SwindleType)> {
+    match type_expression(state, iterable) {
+        Ok((range, SwindleType::Range)) => Ok(((SwindleType::Range, range), SwindleType::Int)),
+        Ok((list, SwindleType::List(typ))) => Ok(((SwindleType::List(typ.clone()), list), *typ)),
+        Ok((string, SwindleType::String)) => {
+            let chars = SwindleType::List(Box::new(SwindleType::Char));
+            let list = Builtin::Runtime("chars_", vec![*string], chars.clone());
+            let list = Box::new(Primary::Builtin(list).to_expression());
+            Ok(((chars, list), SwindleType::Char))
+        }
+        Err(e) => Err(e),
+        _ => throw_error(
+            "can only iterate over lists, strings and ranges".to_string(),
+            state.file_posn,
+        ),
+    }
+}
+
+fn declare_loop_vars(state: &mut TyperState, vars: &[(SwindleType, String)]) -> TyperResult<()> {
+    for (typ, varname) in vars {
+        if state.get(varname).is_some() {
+            return throw_error(
+                "cannot declare a variable twice".to_string(),
+                state.file_posn,
+            );
+        }
+        state.insert(varname.clone(), typ.clone());
+    }
+    Ok(())
+}
+
+/*
+ * A loop collects the values of its body, so the values of breaks out of it have to match
+ */
+fn type_loop_body(
+    state: &mut TyperState,
+    label: Option<String>,
+    body: Body<Parsed>,
+) -> TyperResult<(Body<Typed>, SwindleType)> {
+    let depth = state.loops.len();
+    state.loops.push(label);
+    let result = type_body(state, body);
+    state.loops.pop();
+    let (body, body_ty) = result?;
+
+    let breaks = state.breaks.replace(Vec::new());
+    let (ours, theirs): (Vec<_>, Vec<_>) = breaks.into_iter().partition(|(d, _, _)| *d == depth);
+    state.breaks.replace(theirs);
+    // if the body ends in a jump, the loop only collects the values it breaks with
+    let mut loop_ty = if body.ends_in_jump() {
+        None
+    } else {
+        Some(body_ty)
+    };
+    for (_, typ, posn) in ours {
+        match &loop_ty {
+            None => loop_ty = Some(typ),
+            Some(loop_ty) if *loop_ty == typ => (),
+            Some(_) => {
+                return throw_error("break value type doesn't match loop body".to_string(), posn)
+            }
+        }
+    }
+
+    Ok((body, loop_ty.unwrap_or(SwindleType::Unit)))
+}
+
 fn type_ifexp(
     state: &mut TyperState,
     ifexp: IfExp<Parsed>,
@@ -314,7 +680,7 @@ fn type_body(
                 if have_jumped {
                     return throw_error("unreachable statement".to_string(), state.file_posn);
                 }
-                if let Statement::Break | Statement::Continue = stmt {
+                if let Statement::Break(_, _) | Statement::Continue(_) = stmt {
                     have_jumped = true;
                 }
                 body_type = t.clone();
@@ -374,15 +740,20 @@ fn type_compexp(
             type_addexp(state, *addexp1).and_then(|(a1, t1)| {
                 type_addexp(state, *addexp2).and_then(|(a2, t2)| {
                     let op = match compop {
-                        CompOp::Leq => CompOp::Leq,
-                        CompOp::Lt => CompOp::Lt,
+                        CompOp::Leq(()) => CompOp::Leq(t1.clone()),
+                        CompOp::Lt(()) => CompOp::Lt(t1.clone()),
                         CompOp::Eq(()) => CompOp::Eq(t1.clone()),
                     };
 
                     let result = (Box::new(CompExp::Comp(op, a1, a2)), SwindleType::Bool);
                     match compop {
                         CompOp::Eq(_) => {
-                            if t1 == t2 {
+                            if t1 == t2 && !t1.is_comparable() {
+                                throw_error(
+                                    "can't check equality for maps or sets".to_string(),
+                                    state.file_posn,
+                                )
+                            } else if t1 == t2 {
                                 Ok(result)
                             } else {
                                 throw_error(
@@ -392,9 +763,20 @@ fn type_compexp(
                             }
                         }
                         _ => match (t1, t2) {
-                            (SwindleType::Int, SwindleType::Int) => Ok(result),
+                            (t1, t2) if t1 == t2 && t1.is_number() => Ok(result),
+                            (SwindleType::Char, SwindleType::Char)
+                            | (SwindleType::String, SwindleType::String) => Ok(result),
+                            (SwindleType::List(l1), SwindleType::List(l2))
+                                if l1 == l2 && l1.is_comparable() =>
+                            {
+                                Ok(result)
+                            }
+                            (t1 @ SwindleType::Tuple(_), t2) if t1 == t2 && t1.is_comparable() => {
+                                Ok(result)
+                            }
                             _ => throw_error(
-                                "can only compare integers".to_string(),
+                                "can only compare numbers, chars, strings, lists and tuples"
+                                    .to_string(),
                                 state.file_posn,
                             ),
                         },
@@ -413,20 +795,44 @@ fn type_addexp(
     addexp: AddExp<Parsed>,
 ) -> TyperResult<(Box<AddExp<Typed>>, SwindleType)> {
     match addexp {
-        AddExp::Add(addop, mulexp, addexp) => type_mulexp(state, *mulexp).and_then(|(m, tm)| {
-            type_addexp(state, *addexp).and_then(|(a, ta)| match (addop, tm, ta) {
+        AddExp::Add(addop, addexp, mulexp) => type_addexp(state, *addexp).and_then(|(a, ta)| {
+            type_mulexp(state, *mulexp).and_then(|(m, tm)| match (addop, ta, tm) {
                 (AddOp::Sum(()), SwindleType::String, SwindleType::String) => Ok((
-                    Box::new(AddExp::Add(AddOp::Sum(SwindleType::String), m, a)),
+                    Box::new(AddExp::Add(AddOp::Sum(SwindleType::String), a, m)),
                     SwindleType::String,
                 )),
-                (AddOp::Sum(()), SwindleType::Int, SwindleType::Int) => Ok((
-                    Box::new(AddExp::Add(AddOp::Sum(SwindleType::Int), m, a)),
-                    SwindleType::Int,
-                )),
-                (AddOp::Difference, SwindleType::Int, SwindleType::Int) => Ok((
-                    Box::new(AddExp::Add(AddOp::Difference, m, a)),
-                    SwindleType::Int,
+                (AddOp::Sum(()), SwindleType::List(t1), SwindleType::List(t2)) if t1 == t2 => {
+                    let typ = SwindleType::List(t1);
+                    Ok((Box::new(AddExp::Add(AddOp::Sum(typ.clone()), a, m)), typ))
+                }
+                (AddOp::Sum(()), typ, t2) if typ.is_number() && typ == t2 => {
+                    Ok((Box::new(AddExp::Add(AddOp::Sum(typ.clone()), a, m)), typ))
+                }
+                (AddOp::Difference(()), typ, t2) if typ.is_number() && typ == t2 => Ok((
+                    Box::new(AddExp::Add(AddOp::Difference(typ.clone()), a, m)),
+                    typ,
                 )),
+                (AddOp::Difference(()), SwindleType::Set(t1), SwindleType::Set(t2)) if t1 == t2 => {
+                    let typ = SwindleType::Set(t1);
+                    Ok((
+                        Box::new(AddExp::Add(AddOp::Difference(typ.clone()), a, m)),
+                        typ,
+                    ))
+                }
+                (AddOp::Union(()), SwindleType::Set(t1), SwindleType::Set(t2)) if t1 == t2 => {
+                    let typ = SwindleType::Set(t1);
+                    Ok((Box::new(AddExp::Add(AddOp::Union(typ.clone()), a, m)), typ))
+                }
+                (AddOp::Union(()), typ, t2) if typ.is_integer() && typ == t2 => {
+                    Ok((Box::new(AddExp::Add(AddOp::Union(typ.clone()), a, m)), typ))
+                }
+                (AddOp::Xor(()), typ, t2) if typ.is_integer() && typ == t2 => {
+                    Ok((Box::new(AddExp::Add(AddOp::Xor(typ.clone()), a, m)), typ))
+                }
+                (AddOp::Xor(()), _, _) => throw_error(
+                    "can only xor integers of the same type".to_string(),
+                    state.file_posn,
+                ),
                 _ => throw_error("bad types for addition".to_string(), state.file_posn),
             })
         }),
@@ -441,12 +847,51 @@ fn type_mulexp(
     mulexp: MulExp<Parsed>,
 ) -> TyperResult<(Box<MulExp<Typed>>, SwindleType)> {
     match mulexp {
-        MulExp::Mul(mulop, unary, mulexp) => type_unary(state, *unary).and_then(|(u, tu)| {
-            type_mulexp(state, *mulexp).and_then(|(m, tm)| match (tu, tm) {
-                (SwindleType::Int, SwindleType::Int) => {
-                    Ok((Box::new(MulExp::Mul(mulop, u, m)), SwindleType::Int))
+        MulExp::Mul(mulop, mulexp, unary) => type_mulexp(state, *mulexp).and_then(|(m, tm)| {
+            type_unary(state, *unary).and_then(|(u, tu)| {
+                let mulop = match mulop {
+                    MulOp::Product(()) => MulOp::Product(tm.clone()),
+                    MulOp::Quotient(()) => MulOp::Quotient(tm.clone()),
+                    MulOp::Remainder(()) => MulOp::Remainder(tm.clone()),
+                    MulOp::Intersection(()) => MulOp::Intersection(tm.clone()),
+                    MulOp::ShiftLeft(()) => MulOp::ShiftLeft(tm.clone()),
+                    MulOp::ShiftRight(()) => MulOp::ShiftRight(tm.clone()),
+                };
+                match (mulop, tm, tu) {
+                    (MulOp::Intersection(typ @ SwindleType::Set(_)), t1, t2) if t1 == t2 => {
+                        Ok((Box::new(MulExp::Mul(MulOp::Intersection(typ), m, u)), t1))
+                    }
+                    (
+                        mulop @ (MulOp::Intersection(_)
+                        | MulOp::ShiftLeft(_)
+                        | MulOp::ShiftRight(_)),
+                        typ,
+                        t2,
+                    ) if typ.is_integer() && typ == t2 => {
+                        Ok((Box::new(MulExp::Mul(mulop, m, u)), typ))
+                    }
+                    (MulOp::Intersection(_), _, _) => throw_error(
+                        "can only intersect sets or integers of the same type".to_string(),
+                        state.file_posn,
+                    ),
+                    (MulOp::ShiftLeft(_) | MulOp::ShiftRight(_), _, _) => throw_error(
+                        "can only shift integers of the same type".to_string(),
+                        state.file_posn,
+                    ),
+                    (mulop, typ, t2) if typ.is_number() && typ == t2 => {
+                        Ok((Box::new(MulExp::Mul(mulop, m, u)), typ))
+                    }
+                    // "-" * 40 and [int; 0] * n repeat the string or list
+                    (
+                        MulOp::Product(_),
+                        typ @ (SwindleType::String | SwindleType::List(_)),
+                        SwindleType::Int,
+                    ) => Ok((
+                        Box::new(MulExp::Mul(MulOp::Product(typ.clone()), m, u)),
+                        typ,
+                    )),
+                    _ => throw_error("bad types for multiplication".to_string(), state.file_posn),
                 }
-                _ => throw_error("bad types for multiplication".to_string(), state.file_posn),
             })
         }),
         MulExp::Unary(unary) => {
@@ -460,9 +905,9 @@ fn type_unary(
     unary: Unary<Parsed>,
 ) -> TyperResult<(Box<Unary<Typed>>, SwindleType)> {
     match unary {
-        Unary::Negate(unary) => type_unary(state, *unary).and_then(|(u, t)| match t {
-            SwindleType::Int => Ok((Box::new(Unary::Negate(u)), SwindleType::Int)),
-            _ => throw_error("can only negate integers".to_string(), state.file_posn),
+        Unary::Negate((), unary) => type_unary(state, *unary).and_then(|(u, t)| match t {
+            t if t.is_number() => Ok((Box::new(Unary::Negate(t.clone(), u)), t)),
+            _ => throw_error("can only negate numbers".to_string(), state.file_posn),
         }),
         Unary::Not(unary) => type_unary(state, *unary).and_then(|(u, t)| match t {
             SwindleType::Bool => Ok((Box::new(Unary::Not(u)), SwindleType::Bool)),
@@ -483,8 +928,10 @@ fn type_primary(
             type_expression(state, *expression).map(|(e, t)| (Box::new(Primary::Paren(e)), t))
         }
         Primary::IntLit(n) => Ok((Box::new(Primary::IntLit(n)), SwindleType::Int)),
+        Primary::FloatLit(f) => Ok((Box::new(Primary::FloatLit(f)), SwindleType::Float)),
         Primary::StringLit(s) => Ok((Box::new(Primary::StringLit(s)), SwindleType::String)),
         Primary::BoolLit(b) => Ok((Box::new(Primary::BoolLit(b)), SwindleType::Bool)),
+        Primary::CharLit(c) => Ok((Box::new(Primary::CharLit(c)), SwindleType::Char)),
         Primary::Variable(varname) => match state.get(&varname) {
             Some(typ) => Ok((Box::new(Primary::Variable(varname)), typ)),
             None => throw_error(format!("undeclared variable: {}", varname), state.file_posn),
@@ -494,11 +941,42 @@ fn type_primary(
             type_ifexp(state, ifexp).map(|(i, t)| (Box::new(Primary::IfExp(i)), t))
         }
         Primary::ForExp(forexp) => {
-            let was_in_loop = state.in_loop;
-            state.in_loop = true;
-            let result = type_forexp(state, forexp).map(|(i, t)| (Box::new(Primary::ForExp(i)), t));
-            state.in_loop = was_in_loop;
-            result
+            type_forexp(state, forexp).map(|(i, t)| (Box::new(Primary::ForExp(i)), t))
+        }
+        Primary::ForInExp(forin) => {
+            type_forinexp(state, forin).map(|(i, t)| (Box::new(Primary::ForInExp(i)), t))
+        }
+        Primary::Comprehension(1, forin) => {
+            type_forinexp(state, forin).map(|(i, t)| (Box::new(Primary::Comprehension(1, i)), t))
+        }
+        // { result = [T]; for x in xs { for y in ys { @extend(result, for z in zs { item; }); }; }; result; }
+        Primary::Comprehension(clauses, forin) => {
+            let (forin, mut list_ty) = type_forinexp(state, forin)?;
+            for _ in 1..clauses {
+                list_ty = match list_ty {
+                    SwindleType::List(inner) => *inner,
+                    _ => unreachable!(),
+                };
+            }
+            let item_ty = match &list_ty {
+                SwindleType::List(item) => (**item).clone(),
+                _ => unreachable!(),
+            };
+            let result = state.fresh_name("@comprehension");
+            let empty = Primary::List(item_ty, Vec::new()).to_expression();
+            let forin = flatten_comprehension(forin, clauses, &result);
+            let statements = vec![
+                TaggedStatement::new(
+                    SwindleType::Unit,
+                    Statement::Declare(list_ty.clone(), result.clone(), Box::new(empty)),
+                ),
+                TaggedStatement::new(SwindleType::Unit, Primary::ForInExp(forin).to_statement()),
+                TaggedStatement::new(list_ty.clone(), Primary::Variable(result).to_statement()),
+            ];
+            Ok((
+                Box::new(Primary::StatementExp(Body { statements })),
+                list_ty,
+            ))
         }
         Primary::StatementExp(body) => {
             type_body(state, body).map(|(body, ty)| (Box::new(Primary::StatementExp(body)), ty))
@@ -507,21 +985,82 @@ fn type_primary(
             let (list, list_type, result_type) = match type_primary(state, *list) {
                 Ok((list, SwindleType::String)) => (list, SwindleType::String, SwindleType::String),
                 Ok((list, SwindleType::List(typ))) => (list, SwindleType::List(typ.clone()), *typ),
+                Ok((map, SwindleType::Map(key, value))) => {
+                    (map, SwindleType::Map(key, value.clone()), *value)
+                }
                 Err(e) => return Err(e),
                 _ => return throw_error("bad type for list".to_string(), state.file_posn),
             };
 
-            let index = match type_expression(state, *index) {
-                Ok((index, SwindleType::Int)) => index,
-                Err(e) => return Err(e),
+            let index = match (type_expression(state, *index), &list_type) {
+                (Ok((key, typ)), SwindleType::Map(key_type, _)) if typ == **key_type => key,
+                (Ok((range, SwindleType::Range)), _) => {
+                    // indexing with a range slices out every index in it
+                    let func = match list_type {
+                        SwindleType::String => "index_string_range",
+                        _ => "slice_list_range",
+                    };
+                    let args = box_args(
+                        vec![(*list).to_expression(), *range],
+                        &[list_type.clone(), SwindleType::Range],
+                    );
+                    let slice = Builtin::Runtime(func, args, list_type.clone());
+                    return Ok((Box::new(Primary::Builtin(slice)), list_type));
+                }
+                (Ok(_), SwindleType::Map(_, _)) => {
+                    return throw_error("bad type for map key".to_string(), state.file_posn)
+                }
+                (Ok((index, SwindleType::Int)), _) => index,
+                (Err(e), _) => return Err(e),
                 _ => return throw_error("bad type for list index".to_string(), state.file_posn),
             };
 
+            let builtin = match list_type {
+                SwindleType::String => {
+                    let args = vec![(*list).to_expression(), *index];
+                    Builtin::Checked(state.file_posn, "index_string1", args, SwindleType::String)
+                }
+                _ => Builtin::Index(state.file_posn, list_type, list, index),
+            };
+            Ok((Box::new(Primary::Builtin(builtin)), result_type))
+        }
+        Primary::Slice((), list, low, high) => {
+            let (list, list_type) = match type_primary(state, *list) {
+                Ok((list, typ @ (SwindleType::String | SwindleType::List(_)))) => (list, typ),
+                Err(e) => return Err(e),
+                _ => {
+                    return throw_error(
+                        "can only slice strings and lists".to_string(),
+                        state.file_posn,
+                    )
+                }
+            };
+
+            let mut bounds = Vec::new();
+            for bound in [low, high] {
+                bounds.push(match bound.map(|bound| type_expression(state, *bound)) {
+                    Some(Ok((bound, SwindleType::Int))) => Some(bound),
+                    Some(Err(e)) => return Err(e),
+                    Some(_) => {
+                        return throw_error("bad type for slice bound".to_string(), state.file_posn)
+                    }
+                    None => None,
+                });
+            }
+            let high = bounds.pop().unwrap();
+            let low = bounds.pop().unwrap();
+
             Ok((
-                Box::new(Primary::Index(list_type, list, index)),
-                result_type,
+                Box::new(Primary::Slice(list_type.clone(), list, low, high)),
+                list_type,
             ))
         }
+        Primary::Builtin((func, args))
+            if ["@map", "@filter", "@fold", "@any", "@all"].contains(&func.as_str()) =>
+        {
+            type_function_builtin(state, &func, args)
+        }
+        Primary::Builtin((func, args)) if func == "@sort_by" => type_sort_by(state, args),
         Primary::Builtin((func, args)) => type_builtin(state, func, args)
             .map(|(builtin, typ)| (Box::new(Primary::Builtin(builtin)), typ)),
         Primary::List(typ, items) => {
@@ -548,6 +1087,82 @@ fn type_primary(
                 SwindleType::List(Box::new(typ)),
             ))
         }
+        Primary::Map(key, value, entries) => {
+            let key = type_to_swindle_type(key);
+            let value = type_to_swindle_type(value);
+            let mut new_entries = Vec::new();
+            for (k, v) in entries {
+                match type_expression(state, k).and_then(|(k, tk)| {
+                    type_expression(state, v).map(|(v, tv)| ((*k, *v), tk == key && tv == value))
+                }) {
+                    Ok((entry, true)) => new_entries.push(entry),
+                    Ok((_, false)) => {
+                        return throw_error(
+                            "map entry types don't match".to_string(),
+                            state.file_posn,
+                        )
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+
+            Ok((
+                Box::new(Primary::Map(key.clone(), value.clone(), new_entries)),
+                SwindleType::Map(Box::new(key), Box::new(value)),
+            ))
+        }
+        Primary::Set(typ, items) => {
+            let typ = type_to_swindle_type(typ);
+            let mut new_items = Vec::new();
+            for item in items {
+                match type_expression(state, item) {
+                    Ok((item, item_type)) if item_type == typ => new_items.push(*item),
+                    Ok(_) => {
+                        return throw_error(
+                            "set item types don't match".to_string(),
+                            state.file_posn,
+                        )
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+
+            Ok((
+                Box::new(Primary::Set(typ.clone(), new_items)),
+                SwindleType::Set(Box::new(typ)),
+            ))
+        }
+        Primary::Tuple(types, items) => {
+            let types = types
+                .into_iter()
+                .map(type_to_swindle_type)
+                .collect::<Vec<_>>();
+            if items.len() != types.len() {
+                return throw_error(
+                    "tuple literal has the wrong number of items".to_string(),
+                    state.file_posn,
+                );
+            }
+
+            let mut new_items = Vec::new();
+            for (item, typ) in items.into_iter().zip(&types) {
+                match type_expression(state, item) {
+                    Ok((item, item_type)) if item_type == *typ => new_items.push(*item),
+                    Ok(_) => {
+                        return throw_error(
+                            "tuple item types don't match".to_string(),
+                            state.file_posn,
+                        )
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+
+            Ok((
+                Box::new(Primary::Tuple(types.clone(), new_items)),
+                SwindleType::Tuple(types),
+            ))
+        }
         Primary::Function(_) => unimplemented!(),
         Primary::FunCall(_, _) => unimplemented!(),
     }
@@ -562,11 +1177,12 @@ fn type_builtin(
         "@length" => {
             if args.len() == 1 {
                 type_expression(state, args.pop().unwrap()).and_then(|(arg, typ)| match typ {
-                    SwindleType::List(_) | SwindleType::String => {
-                        Ok((Builtin::Length(typ, arg), SwindleType::Int))
-                    }
+                    SwindleType::List(_)
+                    | SwindleType::String
+                    | SwindleType::Map(_, _)
+                    | SwindleType::Set(_) => Ok((Builtin::Length(typ, arg), SwindleType::Int)),
                     _ => throw_error(
-                        "@length only accepts strings and lists".to_string(),
+                        "@length only accepts strings, lists, maps and sets".to_string(),
                         state.file_posn,
                     ),
                 })
@@ -591,6 +1207,1023 @@ fn type_builtin(
                 SwindleType::Unit,
             ))
         }
-        _ => throw_error(format!("{} is not a builtin", func), state.file_posn),
+        "@assert" => {
+            if args.is_empty() || args.len() > 2 {
+                return throw_error(
+                    "@assert accepts a bool and an optional message".to_string(),
+                    state.file_posn,
+                );
+            }
+
+            let message = if args.len() == 2 {
+                match type_expression(state, args.pop().unwrap()) {
+                    Ok((message, SwindleType::String)) => Some(message),
+                    Err(e) => return Err(e),
+                    _ => {
+                        return throw_error(
+                            "@assert message must be a string".to_string(),
+                            state.file_posn,
+                        )
+                    }
+                }
+            } else {
+                None
+            };
+
+            match type_expression(state, args.pop().unwrap()) {
+                Ok((cond, SwindleType::Bool)) => Ok((
+                    Builtin::Assert(state.file_posn, cond, message),
+                    SwindleType::Unit,
+                )),
+                Err(e) => Err(e),
+                _ => throw_error("@assert only accepts a bool".to_string(), state.file_posn),
+            }
+        }
+        "@panic" => {
+            if args.len() == 1 {
+                type_expression(state, args.pop().unwrap()).and_then(|(arg, typ)| match typ {
+                    SwindleType::String => {
+                        Ok((Builtin::Panic(state.file_posn, arg), SwindleType::Unit))
+                    }
+                    _ => throw_error("@panic only accepts a string".to_string(), state.file_posn),
+                })
+            } else {
+                throw_error(
+                    "@panic only accepts exactly 1 argument".to_string(),
+                    state.file_posn,
+                )
+            }
+        }
+        "@to_string" => type_args(state, &func, args, 1).map(|(args, types)| {
+            (
+                Builtin::Runtime(
+                    to_string_function(&types[0]),
+                    box_args(args, &types),
+                    SwindleType::String,
+                ),
+                SwindleType::String,
+            )
+        }),
+        "@format" => type_format(state, args),
+        "@push" | "@pop" | "@insert" | "@remove" | "@clear" | "@extend" | "@sort" | "@reverse"
+        | "@index_of" | "@contains" | "@min" | "@max" | "@enumerate" | "@zip" => {
+            type_list_builtin(state, &func, args)
+        }
+        "@keys" | "@values" | "@has" | "@add" => type_map_builtin(state, &func, args),
+        "@exit" => {
+            if args.len() == 1 {
+                type_expression(state, args.pop().unwrap()).and_then(|(arg, typ)| match typ {
+                    SwindleType::Int => Ok((Builtin::Exit(arg), SwindleType::Unit)),
+                    _ => throw_error("@exit only accepts an int".to_string(), state.file_posn),
+                })
+            } else {
+                throw_error(
+                    "@exit only accepts exactly 1 argument".to_string(),
+                    state.file_posn,
+                )
+            }
+        }
+        "@to_int" | "@to_float" | "@to_u8" | "@to_i32" | "@to_u32" | "@to_u64" => {
+            let to = match func.as_ref() {
+                "@to_int" => SwindleType::Int,
+                "@to_float" => SwindleType::Float,
+                "@to_u8" => SwindleType::U8,
+                "@to_i32" => SwindleType::I32,
+                "@to_u32" => SwindleType::U32,
+                _ => SwindleType::U64,
+            };
+            type_args(state, &func, args, 1).and_then(|(mut args, mut types)| {
+                let from = types.pop().unwrap();
+                if from.is_number() {
+                    let arg = Box::new(args.pop().unwrap());
+                    Ok((Builtin::Convert(from, to.clone(), arg), to))
+                } else {
+                    throw_error(format!("{} only accepts numbers", func), state.file_posn)
+                }
+            })
+        }
+        _ => match runtime_signature(&func) {
+            Some((rts_func, params, typ)) => {
+                type_args(state, &func, args, params.len()).and_then(|(args, types)| {
+                    if types == params {
+                        Ok((Builtin::Runtime(rts_func, args, typ.clone()), typ))
+                    } else {
+                        let params = params.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+                        throw_error(
+                            format!("{} expects arguments ({})", func, params.join(", ")),
+                            state.file_posn,
+                        )
+                    }
+                })
+            }
+            None => throw_error(format!("{} is not a builtin", func), state.file_posn),
+        },
+    }
+}
+
+fn to_string_function(typ: &SwindleType) -> &'static str {
+    match typ {
+        SwindleType::Int => "to_string_int",
+        SwindleType::String => "to_string_string",
+        SwindleType::Bool => "to_string_bool",
+        SwindleType::Unit => "to_string_unit",
+        SwindleType::Char => "to_string_char",
+        SwindleType::List(_) => "to_string_list",
+        SwindleType::Map(_, _) => "to_string_map",
+        SwindleType::Set(_) => "to_string_set",
+        SwindleType::Tuple(_) => "to_string_tuple",
+        SwindleType::Range => "to_string_range",
+        SwindleType::Float => "to_string_float",
+        SwindleType::U8 | SwindleType::I32 | SwindleType::U32 => "to_string_int",
+        SwindleType::U64 => "to_string_u64",
+    }
+}
+
+/*
+ * @format gets lowered into RTS calls which render each argument,
+ * which then get concatenated with the text of the template
+ */
+fn type_format(
+    state: &mut TyperState,
+    args: Vec<Expression<Parsed>>,
+) -> TyperResult<(Builtin<Typed>, SwindleType)> {
+    let mut args = args.into_iter();
+    // the parser guarantees that the template is a string literal
+    let template = match args.next() {
+        Some(Expression::OrExp(box OrExp::AndExp(box AndExp::CompExp(box CompExp::AddExp(
+            box AddExp::MulExp(box MulExp::Unary(box Unary::Primary(box Primary::StringLit(
+                template,
+            )))),
+        ))))) => template,
+        _ => unreachable!(),
+    };
+    let parts = match split_interpolated(&template) {
+        Ok(parts) => parts,
+        Err(e) => return throw_error(e.to_string(), state.file_posn),
+    };
+
+    let mut rendered = Vec::new();
+    for part in parts {
+        match part {
+            StringPart::Text(text) if text.is_empty() => (),
+            StringPart::Text(text) => rendered.push(Primary::StringLit(text).to_expression()),
+            StringPart::Code(_, code, _) if !code.trim().is_empty() => {
+                return throw_error(
+                    "@format templates can only contain {} placeholders".to_string(),
+                    state.file_posn,
+                )
+            }
+            StringPart::Code(_, _, spec) => {
+                let spec = match parse_format_spec(spec.unwrap_or("")) {
+                    Ok(spec) => spec,
+                    Err(e) => return throw_error(e.to_string(), state.file_posn),
+                };
+                let arg = match args.next() {
+                    Some(arg) => arg,
+                    None => {
+                        return throw_error(
+                            "@format has more placeholders than arguments".to_string(),
+                            state.file_posn,
+                        )
+                    }
+                };
+                let (arg, typ) = type_expression(state, arg)?;
+                rendered.push(format_value(state, *arg, typ, spec)?);
+            }
+        }
+    }
+    if args.next().is_some() {
+        return throw_error(
+            "@format has more arguments than placeholders".to_string(),
+            state.file_posn,
+        );
+    }
+
+    rendered.insert(0, Primary::IntLit(rendered.len() as u64).to_expression());
+    Ok((
+        Builtin::Runtime("concat", rendered, SwindleType::String),
+        SwindleType::String,
+    ))
+}
+
+/*
+ * Renders value as a string according to spec
+ */
+fn format_value(
+    state: &TyperState,
+    value: Expression<Typed>,
+    typ: SwindleType,
+    spec: FormatSpec,
+) -> TyperResult<Expression<Typed>> {
+    let runtime = |func, args| {
+        Primary::Builtin(Builtin::Runtime(func, args, SwindleType::String)).to_expression()
+    };
+
+    // an i32 in another radix is written as 32 bit two's complement, like a u32
+    let value = match (spec.kind, &typ) {
+        (Some('x' | 'X' | 'o' | 'b'), SwindleType::I32) => {
+            let value = Box::new(value);
+            let value = Builtin::Convert(SwindleType::I32, SwindleType::U32, value);
+            box_args(
+                vec![Primary::Builtin(value).to_expression()],
+                &[SwindleType::U32],
+            )
+        }
+        _ => box_args(vec![value], std::slice::from_ref(&typ)),
+    }
+    .pop()
+    .unwrap();
+    let mut rendered = match (spec.kind, &typ) {
+        // the precision of a float is how many digits it gets after the point
+        (None, SwindleType::Float) if spec.precision.is_some() => {
+            let precision = Primary::IntLit(spec.precision.unwrap()).to_expression();
+            runtime("to_string_fixed", vec![value, precision])
+        }
+        (None, _) => runtime(to_string_function(&typ), vec![value]),
+        (Some('?'), SwindleType::String) => runtime("debug_string", vec![value]),
+        (Some('?'), SwindleType::Char) => runtime("debug_char", vec![value]),
+        (Some('?'), SwindleType::List(_)) => runtime("debug_list", vec![value]),
+        (Some('?'), SwindleType::Map(_, _)) => runtime("debug_map", vec![value]),
+        (Some('?'), SwindleType::Set(_)) => runtime("debug_set", vec![value]),
+        (Some('?'), SwindleType::Tuple(_)) => runtime("debug_tuple", vec![value]),
+        (Some('?'), _) => runtime(to_string_function(&typ), vec![value]),
+        (Some(kind), typ) if typ.is_integer() => {
+            let radix = match kind {
+                'x' | 'X' => 16,
+                'o' => 8,
+                _ => 2,
+            };
+            let args = vec![
+                value,
+                Primary::IntLit(radix).to_expression(),
+                Primary::BoolLit(kind == 'X').to_expression(),
+            ];
+            runtime("to_string_radix", args)
+        }
+        (Some(kind), _) => {
+            return throw_error(
+                format!("{{:{}}} can only format integers", kind),
+                state.file_posn,
+            )
+        }
+    };
+
+    if let Some(precision) = spec.precision.filter(|_| typ != SwindleType::Float) {
+        if typ != SwindleType::String {
+            return throw_error(
+                "precision can only be used when formatting strings and floats".to_string(),
+                state.file_posn,
+            );
+        }
+        let args = vec![
+            rendered,
+            Primary::IntLit(0).to_expression(),
+            Primary::IntLit(precision).to_expression(),
+        ];
+        rendered = runtime("index_string2", args);
+    }
+
+    let number = typ.is_number();
+    if spec.zero && !number {
+        return throw_error(
+            "zero padding can only be used when formatting numbers".to_string(),
+            state.file_posn,
+        );
+    }
+    if spec.width > 0 {
+        // numbers are right aligned by default, everything else is left aligned
+        let default_align = if number { '>' } else { '<' };
+        let args = vec![
+            rendered,
+            Primary::CharLit(spec.fill).to_expression(),
+            Primary::CharLit(spec.align.unwrap_or(default_align)).to_expression(),
+            Primary::IntLit(spec.width).to_expression(),
+            Primary::BoolLit(spec.zero).to_expression(),
+        ];
+        rendered = runtime("pad", args);
+    }
+
+    Ok(rendered)
+}
+
+/*
+ * Builtins on lists, which are generic over the item type
+ */
+fn type_list_builtin(
+    state: &mut TyperState,
+    func: &str,
+    args: Vec<Expression<Parsed>>,
+) -> TyperResult<(Builtin<Typed>, SwindleType)> {
+    let count = match func {
+        "@pop" | "@clear" | "@sort" | "@reverse" | "@min" | "@max" | "@enumerate" => 1,
+        "@insert" => 3,
+        _ => 2,
+    };
+    type_args(state, func, args, count).and_then(|(args, types)| {
+        let list = types[0].clone();
+        let item = match &list {
+            SwindleType::List(item) => (**item).clone(),
+            // @contains also works on strings
+            SwindleType::String if func == "@contains" => SwindleType::String,
+            SwindleType::Range if func == "@contains" => SwindleType::Int,
+            // and @remove on maps, where the item is the removed value
+            SwindleType::Map(_, value) if func == "@remove" => (**value).clone(),
+            // and @remove on sets, which says whether the item was there
+            SwindleType::Set(item) if func == "@remove" => (**item).clone(),
+            _ => {
+                return throw_error(
+                    format!("{} only accepts a list as its first argument", func),
+                    state.file_posn,
+                )
+            }
+        };
+
+        let compares = ["@sort", "@index_of", "@contains", "@min", "@max"].contains(&func);
+        let comparable = list.is_comparable();
+        let (rts_func, params, typ) = match func {
+            "@push" => ("push", vec![list, item], SwindleType::Unit),
+            "@insert" => (
+                "insert",
+                vec![list, SwindleType::Int, item],
+                SwindleType::Unit,
+            ),
+            "@clear" => ("clear", vec![list], SwindleType::Unit),
+            "@extend" => ("extend", vec![list.clone(), list], SwindleType::Unit),
+            "@sort" => ("sort", vec![list], SwindleType::Unit),
+            "@reverse" => ("reverse", vec![list], SwindleType::Unit),
+            "@index_of" => ("index_of", vec![list, item], SwindleType::Int),
+            "@enumerate" => {
+                let pair = SwindleType::Tuple(vec![SwindleType::Int, item]);
+                ("enumerate", vec![list], SwindleType::List(Box::new(pair)))
+            }
+            "@zip" => {
+                let other = match &types[1] {
+                    SwindleType::List(other) => (**other).clone(),
+                    _ => {
+                        return throw_error("@zip only accepts lists".to_string(), state.file_posn)
+                    }
+                };
+                let pair = SwindleType::Tuple(vec![item, other]);
+                let params = vec![list, types[1].clone()];
+                ("zip", params, SwindleType::List(Box::new(pair)))
+            }
+            "@contains" if list == SwindleType::String => (
+                "contains_string",
+                vec![SwindleType::String, SwindleType::String],
+                SwindleType::Bool,
+            ),
+            "@contains" if list == SwindleType::Range => (
+                "contains_range",
+                vec![SwindleType::Range, SwindleType::Int],
+                SwindleType::Bool,
+            ),
+            "@contains" => ("contains_list", vec![list, item], SwindleType::Bool),
+            "@remove" if matches!(list, SwindleType::Set(_)) => {
+                ("remove_set", vec![list, item], SwindleType::Bool)
+            }
+            "@remove" if matches!(list, SwindleType::Map(_, _)) => {
+                let key = match &list {
+                    SwindleType::Map(key, _) => (**key).clone(),
+                    _ => unreachable!(),
+                };
+                (item_function("@remove_map", &item), vec![list, key], item)
+            }
+            "@remove" => (
+                item_function(func, &item),
+                vec![list, SwindleType::Int],
+                item,
+            ),
+            _ => (item_function(func, &item), vec![list], item),
+        };
+
+        if types == params && compares && !comparable {
+            throw_error(
+                format!("{} can't compare maps or sets", func),
+                state.file_posn,
+            )
+        } else if types == params {
+            let args = box_args(args, &types);
+            // these panic for an index out of range, a missing key or an empty list
+            let checked = match &types[0] {
+                SwindleType::Set(_) => false,
+                _ => ["@insert", "@remove", "@pop", "@min", "@max"].contains(&func),
+            };
+            let builtin = if checked {
+                Builtin::Checked(state.file_posn, rts_func, args, typ.clone())
+            } else {
+                Builtin::Runtime(rts_func, args, typ.clone())
+            };
+            Ok((builtin, typ))
+        } else {
+            let params = params.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+            throw_error(
+                format!("{} expects arguments ({})", func, params.join(", ")),
+                state.file_posn,
+            )
+        }
+    })
+}
+
+/*
+ * @map, @filter, @fold, @any and @all take a function literal, which gets inlined as the body of
+ * a for loop, so it can use the variables around it
+ */
+fn type_function_builtin(
+    state: &mut TyperState,
+    func: &str,
+    mut args: Vec<Expression<Parsed>>,
+) -> TyperResult<(Box<Primary<Typed>>, SwindleType)> {
+    let count = if func == "@fold" { 3 } else { 2 };
+    if args.len() != count {
+        return throw_error(
+            format!("{} only accepts exactly {} arguments", func, count),
+            state.file_posn,
+        );
+    }
+    let function = match args.pop().unwrap().into_primary() {
+        Some(Primary::Function(function)) => function,
+        _ => {
+            return throw_error(
+                format!(
+                    "{} only accepts a function literal as its last argument",
+                    func
+                ),
+                state.file_posn,
+            )
+        }
+    };
+
+    let mut args = args.into_iter();
+    let (iterable, item_type) = type_iterable(state, args.next().unwrap())?;
+    let mut params = function
+        .params
+        .into_iter()
+        .map(|(typ, varname)| (type_to_swindle_type(typ), varname))
+        .collect::<Vec<_>>();
+    // @fold's function takes the accumulator before the item
+    let init = match args.next().map(|init| type_expression(state, init)) {
+        Some(Ok((init, typ))) if !params.is_empty() && params[0].0 == typ => {
+            Some((typ, init, params.remove(0).1))
+        }
+        Some(Ok(_)) => {
+            return throw_error(
+                "@fold's function has to take its initial value first".to_string(),
+                state.file_posn,
+            )
+        }
+        Some(Err(e)) => return Err(e),
+        None => None,
+    };
+
+    let types = params
+        .iter()
+        .map(|(typ, _)| typ.clone())
+        .collect::<Vec<_>>();
+    let takes_items = match (types.len(), &item_type) {
+        (1, _) => types[0] == item_type,
+        (n, SwindleType::Tuple(items)) => n > 1 && types == *items,
+        _ => false,
+    };
+    if !takes_items {
+        return throw_error(
+            format!("{}'s function has to take the items it goes over", func),
+            state.file_posn,
+        );
+    }
+
+    let mut body_state = state.clone();
+    // the function can't break out of loops around it
+    body_state.loops = Vec::new();
+    let mut declared = params.clone();
+    if let Some((typ, _, acc)) = &init {
+        declared.push((typ.clone(), acc.clone()));
+    }
+    declare_loop_vars(&mut body_state, &declared)?;
+    let (body, body_ty) = type_body(&mut body_state, function.body)?;
+
+    let call = Primary::StatementExp(body);
+    let forin = |tag, statements, collect| {
+        Primary::ForInExp(ForInExp {
+            tag,
+            label: None,
+            vars: params.clone(),
+            iterable,
+            body: Body { statements },
+            collect,
+        })
+    };
+    let assign = |typ: &SwindleType, varname: &String, value: Primary<Typed>| {
+        let var = Box::new(LValue::Variable(varname.clone()));
+        let assign = Expression::Assign(typ.clone(), var, Box::new(value.to_expression()));
+        TaggedStatement::new(typ.clone(), Statement::Expression(Box::new(assign)))
+    };
+    // @fold, @any and @all update a variable declared before the loop, which is their result,
+    // so the loop doesn't collect anything
+    let (typ, var, init, statements) = match (func, init) {
+        ("@map", _) => {
+            let statements = vec![TaggedStatement::new(body_ty.clone(), call.to_statement())];
+            let list_ty = SwindleType::List(Box::new(body_ty.clone()));
+            return Ok((Box::new(forin(body_ty, statements, true)), list_ty));
+        }
+        ("@filter", _) if body_ty == SwindleType::Bool => {
+            let item = match params.len() {
+                1 => Primary::Variable(params[0].1.clone()),
+                _ => {
+                    let vars = params
+                        .iter()
+                        .map(|(_, varname)| Primary::Variable(varname.clone()).to_expression());
+                    Primary::Tuple(types, vars.collect())
+                }
+            };
+            let statements = vec![
+                skip_if(Unary::Not(Box::new(Unary::Primary(Box::new(call))))),
+                TaggedStatement::new(item_type.clone(), item.to_statement()),
+            ];
+            let list_ty = SwindleType::List(Box::new(item_type.clone()));
+            return Ok((Box::new(forin(item_type, statements, true)), list_ty));
+        }
+        // { acc = init; for x in xs { acc = f(acc, x); }; acc; }
+        ("@fold", Some((typ, init, acc))) if body_ty == typ => {
+            let update = assign(&typ, &acc, call);
+            (typ, acc, init, vec![update])
+        }
+        ("@fold", _) => {
+            return throw_error(
+                "@fold's function has to return the type of its initial value".to_string(),
+                state.file_posn,
+            )
+        }
+        // { found = false; for x in xs { if not f(x) { continue; }; found = true; break; }; found; }
+        // and the same for @all, but with the condition and the flag flipped
+        ("@any", _) | ("@all", _) if body_ty == SwindleType::Bool => {
+            let all = func == "@all";
+            let found = state.fresh_name(func);
+            let init = Box::new(Primary::BoolLit(all).to_expression());
+            let call = Unary::Primary(Box::new(call));
+            let statements = vec![
+                skip_if(if all {
+                    call
+                } else {
+                    Unary::Not(Box::new(call))
+                }),
+                assign(&SwindleType::Bool, &found, Primary::BoolLit(!all)),
+                TaggedStatement::new(SwindleType::Unit, Statement::Break(None, None)),
+            ];
+            (SwindleType::Bool, found, init, statements)
+        }
+        _ => {
+            return throw_error(
+                format!("{}'s function has to return a bool", func),
+                state.file_posn,
+            )
+        }
+    };
+    let statements = vec![
+        TaggedStatement::new(
+            SwindleType::Unit,
+            Statement::Declare(typ.clone(), var.clone(), init),
+        ),
+        TaggedStatement::new(
+            SwindleType::Unit,
+            forin(SwindleType::Unit, statements, false).to_statement(),
+        ),
+        TaggedStatement::new(typ.clone(), Primary::Variable(var).to_statement()),
+    ];
+    Ok((Box::new(Primary::StatementExp(Body { statements })), typ))
+}
+
+/*
+ * @sort_by(list, fn(T a, T b) { ... }) sorts the list in place, with the function saying whether
+ * a goes before b. It's a stable binary insertion sort with the function inlined as the comparison:
+ * { xs = list; sorted = [T]; for x in xs {
+ *     low = 0; high = @length(sorted);
+ *     while low < high { mid = (low + high) / 2; if { a = x; b = sorted[mid]; f(a, b); } {
+ *         high = mid; } else { low = mid + 1; }; };
+ *     @insert(sorted, low, x); };
+ *   @clear(xs); @extend(xs, sorted); }
+ */
+fn type_sort_by(
+    state: &mut TyperState,
+    args: Vec<Expression<Parsed>>,
+) -> TyperResult<(Box<Primary<Typed>>, SwindleType)> {
+    if args.len() != 2 {
+        return throw_error(
+            "@sort_by only accepts exactly 2 arguments".to_string(),
+            state.file_posn,
+        );
+    }
+    let mut args = args.into_iter();
+    let (list, list_ty) = type_expression(state, args.next().unwrap())?;
+    let item_ty = match &list_ty {
+        SwindleType::List(item) => (**item).clone(),
+        _ => {
+            return throw_error(
+                "@sort_by only accepts a list as its first argument".to_string(),
+                state.file_posn,
+            )
+        }
+    };
+    let function = match args.next().unwrap().into_primary() {
+        Some(Primary::Function(function)) => function,
+        _ => {
+            return throw_error(
+                "@sort_by only accepts a function literal as its last argument".to_string(),
+                state.file_posn,
+            )
+        }
+    };
+
+    let params = function
+        .params
+        .into_iter()
+        .map(|(typ, varname)| (type_to_swindle_type(typ), varname))
+        .collect::<Vec<_>>();
+    if params.len() != 2 || params.iter().any(|(typ, _)| *typ != item_ty) {
+        return throw_error(
+            "@sort_by's function has to take two items of the list".to_string(),
+            state.file_posn,
+        );
+    }
+    let mut body_state = state.clone();
+    // the function can't break out of loops around it
+    body_state.loops = Vec::new();
+    declare_loop_vars(&mut body_state, &params)?;
+    let (body, body_ty) = type_body(&mut body_state, function.body)?;
+    if body_ty != SwindleType::Bool {
+        return throw_error(
+            "@sort_by's function has to return a bool".to_string(),
+            state.file_posn,
+        );
+    }
+
+    let fresh = || state.fresh_name("@sort_by");
+    let (xs, sorted, x, low, high, mid) = (fresh(), fresh(), fresh(), fresh(), fresh(), fresh());
+    let var = |varname: &String| Primary::Variable(varname.clone());
+    let int = SwindleType::Int;
+    let declare = |typ: &SwindleType, varname: &String, value: Expression<Typed>| {
+        let declare = Statement::Declare(typ.clone(), varname.clone(), Box::new(value));
+        TaggedStatement::new(SwindleType::Unit, declare)
+    };
+    let assign = |varname: &String, value: Expression<Typed>| {
+        let var = Box::new(LValue::Variable(varname.clone()));
+        let assign = Expression::Assign(SwindleType::Int, var, Box::new(value));
+        TaggedStatement::new(SwindleType::Int, Statement::Expression(Box::new(assign)))
+    };
+    let operand = |primary: Primary<Typed>| {
+        Box::new(MulExp::Unary(Box::new(Unary::Primary(Box::new(primary)))))
+    };
+    let call = |builtin: Builtin<Typed>| {
+        TaggedStatement::new(SwindleType::Unit, Primary::Builtin(builtin).to_statement())
+    };
+
+    let (a, b) = (params[0].1.clone(), params[1].1.clone());
+    let sorted_mid = Builtin::Index(
+        state.file_posn,
+        list_ty.clone(),
+        Box::new(var(&sorted)),
+        Box::new(var(&mid).to_expression()),
+    );
+    let mut compare = vec![
+        declare(&item_ty, &a, var(&x).to_expression()),
+        declare(&item_ty, &b, Primary::Builtin(sorted_mid).to_expression()),
+    ];
+    compare.extend(body.statements);
+    let sum = AddExp::Add(
+        AddOp::Sum(int.clone()),
+        Box::new(AddExp::MulExp(operand(var(&low)))),
+        operand(var(&high)),
+    );
+    let halve = MulExp::Mul(
+        MulOp::Quotient(int.clone()),
+        operand(Primary::Paren(Box::new(sum.to_expression()))),
+        Box::new(Unary::Primary(Box::new(Primary::IntLit(2)))),
+    );
+    let next = AddExp::Add(
+        AddOp::Sum(int.clone()),
+        Box::new(AddExp::MulExp(operand(var(&mid)))),
+        operand(Primary::IntLit(1)),
+    );
+    // both branches are assignments, so the if is an int
+    let ifexp = IfExp {
+        tag: int.clone(),
+        cond: Box::new(
+            Primary::StatementExp(Body {
+                statements: compare,
+            })
+            .to_expression(),
+        ),
+        body: Body {
+            statements: vec![assign(&high, var(&mid).to_expression())],
+        },
+        elifs: Vec::new(),
+        els: Body {
+            statements: vec![assign(&low, next.to_expression())],
+        },
+    };
+    let cond = CompExp::Comp(
+        CompOp::Lt(int.clone()),
+        Box::new(AddExp::MulExp(operand(var(&low)))),
+        Box::new(AddExp::MulExp(operand(var(&high)))),
+    );
+    let search = ForExp {
+        tag: SwindleType::Unit,
+        label: None,
+        init: Box::new(Primary::Unit.to_statement()),
+        cond: Box::new(cond.to_expression()),
+        update: Box::new(Primary::Unit.to_expression()),
+        body: Body {
+            statements: vec![
+                declare(&int, &mid, halve.to_expression()),
+                TaggedStatement::new(int.clone(), Primary::IfExp(ifexp).to_statement()),
+                TaggedStatement::new(SwindleType::Unit, Primary::Unit.to_statement()),
+            ],
+        },
+    };
+    let length = Builtin::Length(list_ty.clone(), Box::new(var(&sorted).to_expression()));
+    let insert = box_args(
+        vec![
+            var(&sorted).to_expression(),
+            var(&low).to_expression(),
+            var(&x).to_expression(),
+        ],
+        &[list_ty.clone(), int.clone(), item_ty.clone()],
+    );
+    let insert = Builtin::Checked(state.file_posn, "insert", insert, SwindleType::Unit);
+    let forin = ForInExp {
+        tag: SwindleType::Unit,
+        label: None,
+        vars: vec![(item_ty.clone(), x)],
+        iterable: (list_ty.clone(), Box::new(var(&xs).to_expression())),
+        body: Body {
+            statements: vec![
+                declare(&int, &low, Primary::IntLit(0).to_expression()),
+                declare(&int, &high, Primary::Builtin(length).to_expression()),
+                // the while loop collects units, which get dropped
+                TaggedStatement::new(
+                    SwindleType::List(Box::new(SwindleType::Unit)),
+                    Primary::ForExp(search).to_statement(),
+                ),
+                call(insert),
+            ],
+        },
+        collect: false,
+    };
+    let both = vec![var(&xs).to_expression(), var(&sorted).to_expression()];
+    let statements = vec![
+        declare(&list_ty, &xs, *list),
+        declare(
+            &list_ty,
+            &sorted,
+            Primary::List(item_ty.clone(), Vec::new()).to_expression(),
+        ),
+        TaggedStatement::new(SwindleType::Unit, Primary::ForInExp(forin).to_statement()),
+        call(Builtin::Runtime(
+            "clear",
+            vec![var(&xs).to_expression()],
+            SwindleType::Unit,
+        )),
+        call(Builtin::Runtime("extend", both, SwindleType::Unit)),
+    ];
+    Ok((
+        Box::new(Primary::StatementExp(Body { statements })),
+        SwindleType::Unit,
+    ))
+}
+
+/*
+ * The loops of a comprehension with several for clauses each end in the loop of the next clause,
+ * so the outer ones stop collecting lists of lists, and the innermost list gets added to result
+ */
+fn flatten_comprehension(
+    mut forin: ForInExp<Typed>,
+    clauses: usize,
+    result: &str,
+) -> ForInExp<Typed> {
+    let inner = match forin.body.statements.pop().map(|last| last.statement) {
+        Some(Statement::Expression(inner)) => inner.into_primary(),
+        _ => None,
+    };
+    let last = match inner {
+        Some(Primary::ForInExp(inner)) if clauses == 2 => {
+            let args = vec![
+                Primary::Variable(result.to_string()).to_expression(),
+                Primary::ForInExp(inner).to_expression(),
+            ];
+            Primary::Builtin(Builtin::Runtime("extend", args, SwindleType::Unit)).to_statement()
+        }
+        Some(Primary::ForInExp(inner)) => {
+            Primary::ForInExp(flatten_comprehension(inner, clauses - 1, result)).to_statement()
+        }
+        _ => unreachable!(),
+    };
+    forin
+        .body
+        .statements
+        .push(TaggedStatement::new(SwindleType::Unit, last));
+    forin.tag = SwindleType::Unit;
+    forin.collect = false;
+    forin
+}
+
+/*
+ * if cond { continue; };
+ */
+fn skip_if(cond: Unary<Typed>) -> TaggedStatement<Typed> {
+    let skip = TaggedStatement::new(SwindleType::Unit, Statement::Continue(None));
+    let ifexp = IfExp {
+        tag: SwindleType::Unit,
+        cond: Box::new(cond.to_expression()),
+        body: Body {
+            statements: vec![skip],
+        },
+        elifs: Vec::new(),
+        els: Body::default(),
+    };
+    TaggedStatement::new(SwindleType::Unit, Primary::IfExp(ifexp).to_statement())
+}
+
+/*
+ * Builtins on maps and sets, which are generic over the key and value types
+ * a set is treated as a map whose values are unit
+ */
+fn type_map_builtin(
+    state: &mut TyperState,
+    func: &str,
+    args: Vec<Expression<Parsed>>,
+) -> TyperResult<(Builtin<Typed>, SwindleType)> {
+    let count = if func == "@has" || func == "@add" {
+        2
+    } else {
+        1
+    };
+    type_args(state, func, args, count).and_then(|(args, types)| {
+        let map = types[0].clone();
+        let (key, value) = match (&map, func) {
+            (SwindleType::Map(key, value), "@keys" | "@values" | "@has") => {
+                ((**key).clone(), (**value).clone())
+            }
+            (SwindleType::Set(item), "@has" | "@add") => ((**item).clone(), SwindleType::Unit),
+            (_, "@has") => {
+                return throw_error(
+                    "@has only accepts a map or set as its first argument".to_string(),
+                    state.file_posn,
+                )
+            }
+            (_, "@add") => {
+                return throw_error(
+                    "@add only accepts a set as its first argument".to_string(),
+                    state.file_posn,
+                )
+            }
+            _ => {
+                return throw_error(
+                    format!("{} only accepts a map as its first argument", func),
+                    state.file_posn,
+                )
+            }
+        };
+
+        let (rts_func, params, typ) = match func {
+            "@keys" => ("keys", vec![map], SwindleType::List(Box::new(key))),
+            "@values" => ("values", vec![map], SwindleType::List(Box::new(value))),
+            "@add" => ("add", vec![map, key], SwindleType::Unit),
+            _ => ("has", vec![map, key], SwindleType::Bool),
+        };
+
+        if types == params {
+            Ok((
+                Builtin::Runtime(rts_func, box_args(args, &types), typ.clone()),
+                typ,
+            ))
+        } else {
+            let params = params.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+            throw_error(
+                format!("{} expects arguments ({})", func, params.join(", ")),
+                state.file_posn,
+            )
+        }
+    })
+}
+
+/*
+ * The RTS function for a list builtin which returns one of the items,
+ * since C needs a different function for each return type (narrow integers come back widened)
+ */
+fn item_function(func: &str, item: &SwindleType) -> &'static str {
+    match (func, item) {
+        ("@pop", typ) if typ.is_integer() => "pop_int",
+        ("@pop", SwindleType::Bool) => "pop_bool",
+        ("@pop", SwindleType::Unit) => "pop_unit",
+        ("@pop", SwindleType::Char) => "pop_char",
+        ("@pop", SwindleType::Float) => "pop_float",
+        ("@pop", _) => "pop_rc",
+        ("@remove", typ) if typ.is_integer() => "remove_int",
+        ("@remove", SwindleType::Bool) => "remove_bool",
+        ("@remove", SwindleType::Unit) => "remove_unit",
+        ("@remove", SwindleType::Char) => "remove_char",
+        ("@remove", SwindleType::Float) => "remove_float",
+        ("@remove", _) => "remove_rc",
+        ("@remove_map", typ) if typ.is_integer() => "remove_map_int",
+        ("@remove_map", SwindleType::Bool) => "remove_map_bool",
+        ("@remove_map", SwindleType::Unit) => "remove_map_unit",
+        ("@remove_map", SwindleType::Char) => "remove_map_char",
+        ("@remove_map", SwindleType::Float) => "remove_map_float",
+        ("@remove_map", _) => "remove_map_rc",
+        ("@min", typ) if typ.is_integer() => "min_int",
+        ("@min", SwindleType::Bool) => "min_bool",
+        ("@min", SwindleType::Unit) => "min_unit",
+        ("@min", SwindleType::Char) => "min_char",
+        ("@min", SwindleType::Float) => "min_float",
+        ("@min", _) => "min_rc",
+        ("@max", typ) if typ.is_integer() => "max_int",
+        ("@max", SwindleType::Bool) => "max_bool",
+        ("@max", SwindleType::Unit) => "max_unit",
+        ("@max", SwindleType::Char) => "max_char",
+        ("@max", SwindleType::Float) => "max_float",
+        ("@max", _) => "max_rc",
+        _ => unreachable!(),
+    }
+}
+
+/*
+ * Builtins which are just a call to an RTS function:
+ * the name of the function, its parameter types, and its result type
+ */
+fn runtime_signature(func: &str) -> Option<(&'static str, Vec<SwindleType>, SwindleType)> {
+    use SwindleType::*;
+    let list = |typ| List(Box::new(typ));
+    Some(match func {
+        "@args" => ("args_", vec![], list(String)),
+        "@readln" => ("readln_", vec![], list(String)),
+        "@read_all" => ("read_all_", vec![], String),
+        "@read_file" => ("read_file", vec![String], list(String)),
+        "@write_file" => ("write_file", vec![String, String], Bool),
+        "@append_file" => ("append_file", vec![String, String], Bool),
+        "@file_exists" => ("file_exists", vec![String], Bool),
+        "@chars" => ("chars_", vec![String], list(Char)),
+        "@bytes" => ("bytes_", vec![String], list(Int)),
+        "@parse_int" => ("parse_int", vec![String], list(Int)),
+        "@parse_bool" => ("parse_bool", vec![String], list(Bool)),
+        "@split" => ("split", vec![String, String], list(String)),
+        "@join" => ("join", vec![list(String), String], String),
+        "@trim" => ("trim", vec![String], String),
+        "@find" => ("find", vec![String, String], Int),
+        "@replace" => ("replace", vec![String, String, String], String),
+        "@starts_with" => ("starts_with", vec![String, String], Bool),
+        "@ends_with" => ("ends_with", vec![String, String], Bool),
+        "@to_upper" => ("to_upper", vec![String], String),
+        "@to_lower" => ("to_lower", vec![String], String),
+        _ => return None,
+    })
+}
+
+/*
+ * The RTS keeps every tuple and range behind an RC, so unboxed ones get boxed on the way in,
+ * and narrow integers get widened
+ */
+fn box_args(args: Vec<Expression<Typed>>, types: &[SwindleType]) -> Vec<Expression<Typed>> {
+    args.into_iter()
+        .zip(types)
+        .map(|(arg, typ)| {
+            if typ.is_unboxed() || typ.is_narrow() {
+                Primary::Builtin(Builtin::Box(typ.clone(), Box::new(arg))).to_expression()
+            } else {
+                arg
+            }
+        })
+        .collect()
+}
+
+/*
+ * Typechecks the arguments of a builtin which takes a fixed number of arguments
+ */
+fn type_args(
+    state: &mut TyperState,
+    func: &str,
+    args: Vec<Expression<Parsed>>,
+    count: usize,
+) -> TyperResult<(Vec<Expression<Typed>>, Vec<SwindleType>)> {
+    if args.len() != count {
+        return throw_error(
+            match count {
+                0 => format!("{} doesn't accept any arguments", func),
+                1 => format!("{} only accepts exactly 1 argument", func),
+                _ => format!("{} only accepts exactly {} arguments", func, count),
+            },
+            state.file_posn,
+        );
+    }
+
+    let mut typed_args = Vec::new();
+    let mut types = Vec::new();
+    for arg in args {
+        match type_expression(state, arg) {
+            Ok((arg, typ)) => {
+                typed_args.push(*arg);
+                types.push(typ);
+            }
+            Err(e) => return Err(e),
+        }
     }
+    Ok((typed_args, types))
 }
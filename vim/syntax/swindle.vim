@@ -6,8 +6,8 @@ let b:current_syntax = "swindle"
 
 syntax keyword swindleKeyword and or not
 syntax keyword swindleKeyword if else elif
-syntax keyword swindleKeyword while break continue for loop
-syntax keyword swindleKeyword int string bool unit fn
+syntax keyword swindleKeyword while break continue for loop test
+syntax keyword swindleKeyword int string bool unit char fn
 highlight link swindleKeyword Keyword
 
 syntax keyword swindleBoolean true false
@@ -22,5 +22,8 @@ highlight link swindleNumber Number
 syntax region swindleString start=/\v"/ skip=/\v\\./ end=/\v"/
 highlight link swindleString String
 
+syntax match swindleCharacter "\v'(\\u\{\x+\}|\\.|[^'\\])'"
+highlight link swindleCharacter Character
+
 syntax region swindleBlock start="{" end="}" transparent fold
 syntax region swindleParen start="(" end=")" transparent fold